@@ -14,7 +14,147 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use std::{env, path::Path};
+
+// range of the systemd_v* features, keep in sync with Cargo.toml
+const MIN_VERSION: u32 = 239;
+const MAX_VERSION: u32 = 256;
+
 fn main() {
-    pkg_config::Config::new().probe("libsystemd").unwrap();
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=SD_SYS_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=SD_SYS_STATIC");
+    let auto = env::var_os("CARGO_FEATURE_SYSTEMD_AUTO").is_some();
+    let detected = link(auto);
+    // the detected version only raises the one of the systemd_v* features so
+    // the features stay additive
+    let detected = match detected {
+        Some(detected) if auto => detected_version(&detected),
+        _ => {
+            if auto {
+                println!("cargo:warning=feature systemd_auto requires pkg-config to detect the \
+                          version of libsystemd, falling back to the systemd_v* features");
+            }
+            None
+        }
+    };
+    let version = detected.max(feature_version());
+    for v in MIN_VERSION..=MAX_VERSION {
+        println!("cargo:rustc-check-cfg=cfg(systemd_v{})", v);
+        if version.is_some_and(|version| v <= version) {
+            println!("cargo:rustc-cfg=systemd_v{}", v);
+        }
+    }
+}
+
+// highest version selected by the systemd_v* features
+fn feature_version() -> Option<u32> {
+    (MIN_VERSION..=MAX_VERSION).rev()
+                               .find(|v| env::var_os(format!("CARGO_FEATURE_SYSTEMD_V{}", v)).is_some())
+}
+
+// Emits the link instructions for libsystemd and returns the version reported
+// by pkg-config if it has been consulted.
+//
+// - docs.rs does not provide libsystemd: nothing is linked
+// - feature dlopen: nothing is linked, pkg-config is only queried for the
+//   version with feature systemd_auto
+// - SD_SYS_LIB_DIR: libsystemd is linked from the given directory, pkg-config
+//   is not used
+// - otherwise pkg-config is used; if it fails, libsystemd is linked from the
+//   default search path of the linker
+//
+// Feature static or SD_SYS_STATIC select libsystemd.a over libsystemd.so. Only
+// pkg-config knows the libraries libsystemd.a depends on.
+fn link(auto: bool) -> Option<String> {
+    let docs = env::var_os("DOCS_RS").is_some();
+    let dlopen = env::var_os("CARGO_FEATURE_DLOPEN").is_some();
+    let statik = env::var_os("CARGO_FEATURE_STATIC").is_some()
+                 || env::var_os("SD_SYS_STATIC").is_some_and(|value| value != "0");
+    if docs || (dlopen && !auto) {
+        return None;
+    }
+    if let Some(directory) = env::var_os("SD_SYS_LIB_DIR").filter(|_| !dlopen) {
+        if !Path::new(&directory).is_dir() {
+            println!("cargo:warning=SD_SYS_LIB_DIR {} is not a directory",
+                     Path::new(&directory).display());
+        }
+        println!("cargo:rustc-link-search=native={}",
+                 Path::new(&directory).display());
+        link_library("systemd", statik);
+        return None;
+    }
+    match pkg_config::Config::new().cargo_metadata(!dlopen && !statik)
+                                   .statik(statik)
+                                   .probe("libsystemd")
+    {
+        Ok(library) => {
+            if statik && !dlopen {
+                link_static(&library);
+            }
+            Some(library.version)
+        },
+        Err(error) if dlopen => {
+            println!("cargo:warning=pkg-config failed to find libsystemd: {}",
+                     error.to_string().lines().next().unwrap_or(""));
+            None
+        },
+        Err(error) => {
+            for line in error.to_string()
+                             .lines()
+                             .filter(|line| !line.trim().is_empty())
+            {
+                println!("cargo:warning={}", line.trim());
+            }
+            println!("cargo:warning=pkg-config failed to find libsystemd, linking against \
+                      libsystemd from the default search path instead. If linking fails, install \
+                      the development package of libsystemd (libsystemd-dev, systemd-devel), set \
+                      PKG_CONFIG_PATH or SD_SYS_LIB_DIR, or enable feature dlopen.");
+            link_library("systemd", statik);
+            None
+        }
+    }
+}
+
+// Links libsystemd.a and the libraries reported by `pkg-config --static`.
+//
+// pkg-config omits the system library directories and the pkg-config crate
+// therefore links libraries found there dynamically; the linker is left to
+// resolve the static archives from its own search path instead.
+fn link_static(library: &pkg_config::Library) {
+    for path in &library.link_paths {
+        println!("cargo:rustc-link-search=native={}", path.display());
+    }
+    for name in &library.libs {
+        // the C runtime is provided by the target
+        let runtime = ["c", "dl", "m", "pthread", "rt"].contains(&name.as_str());
+        link_library(name, !runtime);
+    }
+}
+
+// static libraries are not bundled into the rlib so they do not have to be
+// located by rustc
+fn link_library(name: &str, statik: bool) {
+    if statik {
+        println!("cargo:rustc-link-lib=static:-bundle={}", name);
+    } else {
+        println!("cargo:rustc-link-lib={}", name);
+    }
+}
+
+// pkg-config reports the plain major version of libsystemd, e.g. "252"; some
+// distributions append a patch level or suffix
+fn detected_version(version: &str) -> Option<u32> {
+    let major = version.split(|c: char| !c.is_ascii_digit())
+                       .next()
+                       .unwrap_or("");
+    match major.parse::<u32>() {
+        Ok(v) if v >= MIN_VERSION => Some(v.min(MAX_VERSION)),
+        _ => {
+            println!("cargo:warning=libsystemd {} is not supported, falling back to the \
+                      systemd_v* features",
+                     version);
+            None
+        }
+    }
 }
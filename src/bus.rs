@@ -0,0 +1,1784 @@
+// sd-sys: FFI bindings to systemd for sd-id128 & sd-journal
+// Copyright (C) 2020 Christian Klaue [mail@ck76.de]
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use super::id128::sd_id128;
+use libc::{c_char, c_int, c_uint, c_void, size_t};
+#[cfg(feature = "va_list")]
+use std::ffi::VaList;
+use std::{mem, ptr};
+
+/// FFI data type mapping for a bus connection as defined in libsystemd
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct sd_bus {
+    _unused: [u8; 0]
+}
+
+/// FFI data type mapping for a bus message as defined in libsystemd
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct sd_bus_message {
+    _unused: [u8; 0]
+}
+
+/// FFI data type mapping for a slot as defined in libsystemd
+///
+/// A slot represents a registration of a callback (object, match, pending
+/// call, ...) with a bus connection.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct sd_bus_slot {
+    _unused: [u8; 0]
+}
+
+/// `typedef int (*sd_bus_message_handler_t)(sd_bus_message *m, void *userdata,
+///                                 sd_bus_error *ret_error);`
+#[allow(non_camel_case_types)]
+pub type sd_bus_message_handler_t = Option<unsafe extern "C" fn(message: *mut sd_bus_message,
+                                                                userdata: *mut c_void,
+                                                                error: *mut sd_bus_error)
+                                                                -> c_int>;
+
+/// `typedef void (*sd_bus_destroy_t)(void *userdata);`
+///
+/// Registered with `sd_bus_slot_set_destroy_callback` to release the userdata
+/// of a slot, e.g. a `Box` passed as raw pointer, once the slot is freed.
+#[allow(non_camel_case_types)]
+pub type sd_bus_destroy_t = Option<unsafe extern "C" fn(userdata: *mut c_void)>;
+
+/// `typedef int (*sd_bus_property_get_t)(sd_bus *bus, const char *path,
+///             const char *interface, const char *property,
+///             sd_bus_message *reply, void *userdata,
+///             sd_bus_error *ret_error);`
+#[allow(non_camel_case_types)]
+pub type sd_bus_property_get_t = Option<unsafe extern "C" fn(bus: *mut sd_bus,
+                                                             path: *const c_char,
+                                                             interface: *const c_char,
+                                                             property: *const c_char,
+                                                             reply: *mut sd_bus_message,
+                                                             userdata: *mut c_void,
+                                                             error: *mut sd_bus_error)
+                                                             -> c_int>;
+
+/// `typedef int (*sd_bus_property_set_t)(sd_bus *bus, const char *path,
+///             const char *interface, const char *property,
+///             sd_bus_message *value, void *userdata,
+///             sd_bus_error *ret_error);`
+#[allow(non_camel_case_types)]
+pub type sd_bus_property_set_t = Option<unsafe extern "C" fn(bus: *mut sd_bus,
+                                                             path: *const c_char,
+                                                             interface: *const c_char,
+                                                             property: *const c_char,
+                                                             value: *mut sd_bus_message,
+                                                             userdata: *mut c_void,
+                                                             error: *mut sd_bus_error)
+                                                             -> c_int>;
+
+/// `typedef int (*sd_bus_object_find_t)(sd_bus *bus, const char *path,
+///             const char *interface, void *userdata, void **ret_found,
+///             sd_bus_error *ret_error);`
+#[allow(non_camel_case_types)]
+pub type sd_bus_object_find_t = Option<unsafe extern "C" fn(bus: *mut sd_bus,
+                                                            path: *const c_char,
+                                                            interface: *const c_char,
+                                                            userdata: *mut c_void,
+                                                            found: *mut *mut c_void,
+                                                            error: *mut sd_bus_error)
+                                                            -> c_int>;
+
+/// `typedef int (*sd_bus_node_enumerator_t)(sd_bus *bus, const char *prefix,
+///             void *userdata, char ***ret_nodes, sd_bus_error *ret_error);`
+#[allow(non_camel_case_types)]
+pub type sd_bus_node_enumerator_t = Option<unsafe extern "C" fn(bus: *mut sd_bus,
+                                                                prefix: *const c_char,
+                                                                userdata: *mut c_void,
+                                                                nodes: *mut *mut *mut c_char,
+                                                                error: *mut sd_bus_error)
+                                                                -> c_int>;
+
+/// `typedef struct { const char *name; const char *message;
+///             int _need_free; } sd_bus_error;`
+///
+/// Initialize with [`SD_BUS_ERROR_NULL`] and release with `sd_bus_error_free`.
+///
+/// <https://www.freedesktop.org/software/systemd/man/sd_bus_error.html#>
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Debug)]
+pub struct sd_bus_error {
+    pub name:       *const c_char,
+    pub message:    *const c_char,
+    pub _need_free: c_int
+}
+
+/// `#define SD_BUS_ERROR_NULL ((const sd_bus_error) {})`
+pub const SD_BUS_ERROR_NULL: sd_bus_error = sd_bus_error { name:       ptr::null(),
+                                                           message:    ptr::null(),
+                                                           _need_free: 0 };
+
+// flags of sd_bus_request_name
+#[allow(clippy::identity_op)]
+pub const SD_BUS_NAME_REPLACE_EXISTING: u64 = 1 << 0;
+pub const SD_BUS_NAME_ALLOW_REPLACEMENT: u64 = 1 << 1;
+pub const SD_BUS_NAME_QUEUE: u64 = 1 << 2;
+
+pub const SD_BUS_MESSAGE_METHOD_CALL: u8 = 1;
+pub const SD_BUS_MESSAGE_METHOD_RETURN: u8 = 2;
+pub const SD_BUS_MESSAGE_METHOD_ERROR: u8 = 3;
+pub const SD_BUS_MESSAGE_SIGNAL: u8 = 4;
+
+// D-Bus type signature characters
+pub const SD_BUS_TYPE_INVALID: c_char = 0;
+pub const SD_BUS_TYPE_BYTE: c_char = b'y' as c_char;
+pub const SD_BUS_TYPE_BOOLEAN: c_char = b'b' as c_char;
+pub const SD_BUS_TYPE_INT16: c_char = b'n' as c_char;
+pub const SD_BUS_TYPE_UINT16: c_char = b'q' as c_char;
+pub const SD_BUS_TYPE_INT32: c_char = b'i' as c_char;
+pub const SD_BUS_TYPE_UINT32: c_char = b'u' as c_char;
+pub const SD_BUS_TYPE_INT64: c_char = b'x' as c_char;
+pub const SD_BUS_TYPE_UINT64: c_char = b't' as c_char;
+pub const SD_BUS_TYPE_DOUBLE: c_char = b'd' as c_char;
+pub const SD_BUS_TYPE_STRING: c_char = b's' as c_char;
+pub const SD_BUS_TYPE_OBJECT_PATH: c_char = b'o' as c_char;
+pub const SD_BUS_TYPE_SIGNATURE: c_char = b'g' as c_char;
+pub const SD_BUS_TYPE_UNIX_FD: c_char = b'h' as c_char;
+pub const SD_BUS_TYPE_ARRAY: c_char = b'a' as c_char;
+pub const SD_BUS_TYPE_VARIANT: c_char = b'v' as c_char;
+pub const SD_BUS_TYPE_STRUCT: c_char = b'r' as c_char;
+pub const SD_BUS_TYPE_STRUCT_BEGIN: c_char = b'(' as c_char;
+pub const SD_BUS_TYPE_STRUCT_END: c_char = b')' as c_char;
+pub const SD_BUS_TYPE_DICT_ENTRY: c_char = b'e' as c_char;
+pub const SD_BUS_TYPE_DICT_ENTRY_BEGIN: c_char = b'{' as c_char;
+pub const SD_BUS_TYPE_DICT_ENTRY_END: c_char = b'}' as c_char;
+
+// flags of sd_bus_message_dump
+#[cfg(systemd_v245)]
+#[allow(clippy::identity_op)]
+pub const SD_BUS_MESSAGE_DUMP_WITH_HEADER: u64 = 1 << 0;
+#[cfg(systemd_v245)]
+pub const SD_BUS_MESSAGE_DUMP_SUBTREE_ONLY: u64 = 1 << 1;
+
+// kinds of sd_bus_vtable entries
+pub const _SD_BUS_VTABLE_START: u8 = b'<';
+pub const _SD_BUS_VTABLE_END: u8 = b'>';
+pub const _SD_BUS_VTABLE_METHOD: u8 = b'M';
+pub const _SD_BUS_VTABLE_SIGNAL: u8 = b'S';
+pub const _SD_BUS_VTABLE_PROPERTY: u8 = b'P';
+pub const _SD_BUS_VTABLE_WRITABLE_PROPERTY: u8 = b'W';
+
+// flags of sd_bus_vtable entries
+#[allow(clippy::identity_op)]
+pub const SD_BUS_VTABLE_DEPRECATED: u64 = 1 << 0;
+pub const SD_BUS_VTABLE_HIDDEN: u64 = 1 << 1;
+pub const SD_BUS_VTABLE_UNPRIVILEGED: u64 = 1 << 2;
+pub const SD_BUS_VTABLE_METHOD_NO_REPLY: u64 = 1 << 3;
+pub const SD_BUS_VTABLE_PROPERTY_CONST: u64 = 1 << 4;
+pub const SD_BUS_VTABLE_PROPERTY_EMITS_CHANGE: u64 = 1 << 5;
+pub const SD_BUS_VTABLE_PROPERTY_EMITS_INVALIDATION: u64 = 1 << 6;
+pub const SD_BUS_VTABLE_PROPERTY_EXPLICIT: u64 = 1 << 7;
+#[cfg(systemd_v245)]
+pub const SD_BUS_VTABLE_SENSITIVE: u64 = 1 << 8;
+#[cfg(systemd_v246)]
+pub const SD_BUS_VTABLE_ABSOLUTE_OFFSET: u64 = 1 << 9;
+pub const _SD_BUS_VTABLE_CAPABILITY_MASK: u64 = 0xFFFF << 40;
+
+// features of the vtable announced by SD_BUS_VTABLE_START
+#[allow(clippy::identity_op)]
+pub const _SD_BUS_VTABLE_PARAM_NAMES: u64 = 1 << 0;
+
+/// `#define SD_BUS_VTABLE_CAPABILITY(x) ((uint64_t) (((x)+1) & 0xFFFF) << 40)`
+///
+/// Flag requiring the capability `capability` to call a method or set a
+/// property instead of `CAP_SYS_ADMIN`.
+#[allow(non_snake_case)]
+pub const fn SD_BUS_VTABLE_CAPABILITY(capability: c_int) -> u64 {
+    (((capability + 1) & 0xFFFF) as u64) << 40
+}
+
+/// `struct { size_t element_size; uint64_t features;
+///             const unsigned *vtable_format_reference; } start;`
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct sd_bus_vtable_start {
+    pub element_size:            size_t,
+    pub features:                u64,
+    pub vtable_format_reference: *const c_uint
+}
+
+/// `struct { size_t _reserved; } end;`
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct sd_bus_vtable_end {
+    pub _reserved: size_t
+}
+
+/// `struct { const char *member; const char *signature; const char *result;
+///             sd_bus_message_handler_t handler; size_t offset;
+///             const char *names; } method;`
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct sd_bus_vtable_method {
+    pub member:    *const c_char,
+    pub signature: *const c_char,
+    pub result:    *const c_char,
+    pub handler:   sd_bus_message_handler_t,
+    pub offset:    size_t,
+    pub names:     *const c_char
+}
+
+/// `struct { const char *member; const char *signature;
+///             const char *names; } signal;`
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct sd_bus_vtable_signal {
+    pub member:    *const c_char,
+    pub signature: *const c_char,
+    pub names:     *const c_char
+}
+
+/// `struct { const char *member; const char *signature;
+///             sd_bus_property_get_t get; sd_bus_property_set_t set;
+///             size_t offset; } property;`
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct sd_bus_vtable_property {
+    pub member:    *const c_char,
+    pub signature: *const c_char,
+    pub get:       sd_bus_property_get_t,
+    pub set:       sd_bus_property_set_t,
+    pub offset:    size_t
+}
+
+/// The union `x` of `sd_bus_vtable`, the member is selected by the kind of the
+/// entry.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union sd_bus_vtable_union {
+    pub start:    sd_bus_vtable_start,
+    pub end:      sd_bus_vtable_end,
+    pub method:   sd_bus_vtable_method,
+    pub signal:   sd_bus_vtable_signal,
+    pub property: sd_bus_vtable_property
+}
+
+/// `struct sd_bus_vtable { uint8_t type:8; uint64_t flags:56;
+///             union {...} x; };`
+///
+/// An entry of the vtable passed to `sd_bus_add_object_vtable` and
+/// `sd_bus_add_fallback_vtable`. The constructors mirror the C macros
+/// `SD_BUS_VTABLE_START`, `SD_BUS_METHOD`, `SD_BUS_SIGNAL`, `SD_BUS_PROPERTY`,
+/// ... and are `const`, so a vtable can be a `static` array which must begin
+/// with [`start`](sd_bus_vtable::start) and end with
+/// [`end`](sd_bus_vtable::end).
+///
+/// Argument names are passed as one string of NUL terminated names: the names
+/// of the input arguments, then the names of the output arguments, then a
+/// final empty string, e.g. `b"name\0count\0ok\0\0"` for a method with
+/// signature `"su"` (inputs `name` and `count`) and result `"b"` (output
+/// `ok`). Signals only have input arguments. This corresponds to
+/// `SD_BUS_METHOD_WITH_NAMES` and `SD_BUS_ARGS` in C, where the compiler adds
+/// the final NUL to the string literal.
+///
+/// The layout is the one introduced with systemd 242, the value of
+/// `sd_bus_object_vtable_format`. `SD_BUS_VTABLE_START` refers to that symbol
+/// to enforce the layout at link time, [`start`](sd_bus_vtable::start) refers
+/// to a copy of its value instead so vtables work with feature `dlopen` as
+/// well. libsystemd reads the argument names only if `element_size` covers the
+/// fields added in 242 and `features` contains `_SD_BUS_VTABLE_PARAM_NAMES`,
+/// both set by [`start`](sd_bus_vtable::start).
+///
+/// <https://www.freedesktop.org/software/systemd/man/sd_bus_add_object.html#>
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct sd_bus_vtable {
+    type_and_flags: u64,
+    pub x:          sd_bus_vtable_union
+}
+
+// the vtable only holds pointers to static strings and functions
+unsafe impl Sync for sd_bus_vtable {}
+
+// value of `sd_bus_object_vtable_format`
+const VTABLE_FORMAT: &c_uint = &242;
+
+impl sd_bus_vtable {
+    // packs the bitfields `uint8_t type:8; uint64_t flags:56;`
+    #[cfg(target_endian = "little")]
+    const fn type_and_flags(kind: u8, flags: u64) -> u64 {
+        kind as u64 | flags << 8
+    }
+
+    #[cfg(target_endian = "big")]
+    const fn type_and_flags(kind: u8, flags: u64) -> u64 {
+        (kind as u64) << 56 | (flags & 0x00FF_FFFF_FFFF_FFFF)
+    }
+
+    /// `SD_BUS_VTABLE_START(flags)`
+    pub const fn start(flags: u64) -> Self {
+        let start = sd_bus_vtable_start { element_size:            mem::size_of::<Self>(),
+                                          features:                _SD_BUS_VTABLE_PARAM_NAMES,
+                                          vtable_format_reference: VTABLE_FORMAT };
+        Self { type_and_flags: Self::type_and_flags(_SD_BUS_VTABLE_START, flags),
+               x:              sd_bus_vtable_union { start } }
+    }
+
+    /// `SD_BUS_VTABLE_END`
+    pub const fn end() -> Self {
+        let end = sd_bus_vtable_end { _reserved: 0 };
+        Self { type_and_flags: Self::type_and_flags(_SD_BUS_VTABLE_END, 0),
+               x:              sd_bus_vtable_union { end } }
+    }
+
+    /// `SD_BUS_METHOD(member, signature, result, handler, flags)`
+    pub const fn method(member: *const c_char,
+                        signature: *const c_char,
+                        result: *const c_char,
+                        handler: sd_bus_message_handler_t,
+                        flags: u64)
+                        -> Self {
+        Self::method_with_names(member, signature, result, ptr::null(), handler, 0, flags)
+    }
+
+    /// `SD_BUS_METHOD_WITH_NAMES_OFFSET(member, signature, in_names, result,
+    ///             out_names, handler, offset, flags)`
+    pub const fn method_with_names(member: *const c_char,
+                                   signature: *const c_char,
+                                   result: *const c_char,
+                                   names: *const c_char,
+                                   handler: sd_bus_message_handler_t,
+                                   offset: size_t,
+                                   flags: u64)
+                                   -> Self {
+        let method = sd_bus_vtable_method { member,
+                                            signature,
+                                            result,
+                                            handler,
+                                            offset,
+                                            names };
+        Self { type_and_flags: Self::type_and_flags(_SD_BUS_VTABLE_METHOD, flags),
+               x:              sd_bus_vtable_union { method } }
+    }
+
+    /// `SD_BUS_SIGNAL(member, signature, flags)`
+    pub const fn signal(member: *const c_char, signature: *const c_char, flags: u64) -> Self {
+        Self::signal_with_names(member, signature, ptr::null(), flags)
+    }
+
+    /// `SD_BUS_SIGNAL_WITH_NAMES(member, signature, names, flags)`
+    pub const fn signal_with_names(member: *const c_char,
+                                   signature: *const c_char,
+                                   names: *const c_char,
+                                   flags: u64)
+                                   -> Self {
+        let signal = sd_bus_vtable_signal { member,
+                                            signature,
+                                            names };
+        Self { type_and_flags: Self::type_and_flags(_SD_BUS_VTABLE_SIGNAL, flags),
+               x:              sd_bus_vtable_union { signal } }
+    }
+
+    /// `SD_BUS_PROPERTY(member, signature, get, offset, flags)`
+    pub const fn property(member: *const c_char,
+                          signature: *const c_char,
+                          get: sd_bus_property_get_t,
+                          offset: size_t,
+                          flags: u64)
+                          -> Self {
+        let property = sd_bus_vtable_property { member,
+                                                signature,
+                                                get,
+                                                set: None,
+                                                offset };
+        Self { type_and_flags: Self::type_and_flags(_SD_BUS_VTABLE_PROPERTY, flags),
+               x:              sd_bus_vtable_union { property } }
+    }
+
+    /// `SD_BUS_WRITABLE_PROPERTY(member, signature, get, set, offset, flags)`
+    pub const fn writable_property(member: *const c_char,
+                                   signature: *const c_char,
+                                   get: sd_bus_property_get_t,
+                                   set: sd_bus_property_set_t,
+                                   offset: size_t,
+                                   flags: u64)
+                                   -> Self {
+        let property = sd_bus_vtable_property { member,
+                                                signature,
+                                                get,
+                                                set,
+                                                offset };
+        Self { type_and_flags: Self::type_and_flags(_SD_BUS_VTABLE_WRITABLE_PROPERTY, flags),
+               x:              sd_bus_vtable_union { property } }
+    }
+
+    /// Kind of the entry, one of the `_SD_BUS_VTABLE_*` characters.
+    #[cfg(target_endian = "little")]
+    pub const fn kind(&self) -> u8 {
+        self.type_and_flags as u8
+    }
+
+    /// Kind of the entry, one of the `_SD_BUS_VTABLE_*` characters.
+    #[cfg(target_endian = "big")]
+    pub const fn kind(&self) -> u8 {
+        (self.type_and_flags >> 56) as u8
+    }
+
+    /// Flags of the entry.
+    #[cfg(target_endian = "little")]
+    pub const fn flags(&self) -> u64 {
+        self.type_and_flags >> 8
+    }
+
+    /// Flags of the entry.
+    #[cfg(target_endian = "big")]
+    pub const fn flags(&self) -> u64 {
+        self.type_and_flags & 0x00FF_FFFF_FFFF_FFFF
+    }
+}
+
+// variadic functions, not available with feature `dlopen` (see `sd_extern!`)
+#[cfg(not(feature = "dlopen"))]
+extern "C" {
+    /// `int sd_bus_call_method(sd_bus *bus, const char *destination,
+    ///             const char *path, const char *interface, const char *member,
+    ///             sd_bus_error *ret_error, sd_bus_message **reply,
+    ///             const char *types, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_call_method.html#>
+    pub fn sd_bus_call_method(bus: *mut sd_bus,
+                              destination: *const c_char,
+                              path: *const c_char,
+                              interface: *const c_char,
+                              member: *const c_char,
+                              error: *mut sd_bus_error,
+                              reply: *mut *mut sd_bus_message,
+                              types: *const c_char,
+                              ...)
+                              -> c_int;
+    /// `int sd_bus_reply_method_return(sd_bus_message *call, const char *types,
+    ///                                 …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_reply_method_return.html#>
+    pub fn sd_bus_reply_method_return(call: *mut sd_bus_message,
+                                      types: *const c_char,
+                                      ...)
+                                      -> c_int;
+    /// `int sd_bus_reply_method_errorf(sd_bus_message *call, const char *name,
+    ///                                 const char *format, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_reply_method_error.html#>
+    pub fn sd_bus_reply_method_errorf(call: *mut sd_bus_message,
+                                      name: *const c_char,
+                                      format: *const c_char,
+                                      ...)
+                                      -> c_int;
+    /// `int sd_bus_message_append(sd_bus_message *m, const char *types, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_append.html#>
+    pub fn sd_bus_message_append(message: *mut sd_bus_message, types: *const c_char, ...) -> c_int;
+    /// `int sd_bus_message_read(sd_bus_message *m, const char *types, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_read.html#>
+    pub fn sd_bus_message_read(message: *mut sd_bus_message, types: *const c_char, ...) -> c_int;
+    /// `int sd_bus_error_setf(sd_bus_error *e, const char *name,
+    ///                                 const char *format, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_error.html#>
+    pub fn sd_bus_error_setf(error: *mut sd_bus_error,
+                             name: *const c_char,
+                             format: *const c_char,
+                             ...)
+                             -> c_int;
+    /// `int sd_bus_error_has_names_sentinel(const sd_bus_error *e, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_error.html#>
+    #[cfg(systemd_v247)]
+    pub fn sd_bus_error_has_names_sentinel(error: *const sd_bus_error, ...) -> c_int;
+    /// `int sd_bus_call_method_async(sd_bus *bus, sd_bus_slot **slot,
+    ///             const char *destination, const char *path,
+    ///             const char *interface, const char *member,
+    ///             sd_bus_message_handler_t callback, void *userdata,
+    ///             const char *types, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_call_method.html#>
+    pub fn sd_bus_call_method_async(bus: *mut sd_bus,
+                                    slot: *mut *mut sd_bus_slot,
+                                    destination: *const c_char,
+                                    path: *const c_char,
+                                    interface: *const c_char,
+                                    member: *const c_char,
+                                    callback: sd_bus_message_handler_t,
+                                    userdata: *mut c_void,
+                                    types: *const c_char,
+                                    ...)
+                                    -> c_int;
+    /// `int sd_bus_emit_signal(sd_bus *bus, const char *path,
+    ///             const char *interface, const char *member,
+    ///             const char *types, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_emit_signal.html#>
+    pub fn sd_bus_emit_signal(bus: *mut sd_bus,
+                              path: *const c_char,
+                              interface: *const c_char,
+                              member: *const c_char,
+                              types: *const c_char,
+                              ...)
+                              -> c_int;
+    /// `int sd_bus_emit_properties_changed(sd_bus *bus, const char *path,
+    ///             const char *interface, const char *name, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_emit_signal.html#>
+    pub fn sd_bus_emit_properties_changed(bus: *mut sd_bus,
+                                          path: *const c_char,
+                                          interface: *const c_char,
+                                          name: *const c_char,
+                                          ...)
+                                          -> c_int;
+    /// `int sd_bus_emit_interfaces_added(sd_bus *bus, const char *path,
+    ///                                 const char *interface, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_emit_signal.html#>
+    pub fn sd_bus_emit_interfaces_added(bus: *mut sd_bus,
+                                        path: *const c_char,
+                                        interface: *const c_char,
+                                        ...)
+                                        -> c_int;
+    /// `int sd_bus_emit_interfaces_removed(sd_bus *bus, const char *path,
+    ///                                 const char *interface, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_emit_signal.html#>
+    pub fn sd_bus_emit_interfaces_removed(bus: *mut sd_bus,
+                                          path: *const c_char,
+                                          interface: *const c_char,
+                                          ...)
+                                          -> c_int;
+}
+
+sd_extern! {
+    /// `int sd_bus_default(sd_bus **bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_default.html#>
+    pub fn sd_bus_default(bus: *mut *mut sd_bus) -> c_int;
+    /// `int sd_bus_default_user(sd_bus **bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_default.html#>
+    pub fn sd_bus_default_user(bus: *mut *mut sd_bus) -> c_int;
+    /// `int sd_bus_default_system(sd_bus **bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_default.html#>
+    pub fn sd_bus_default_system(bus: *mut *mut sd_bus) -> c_int;
+    /// `int sd_bus_open(sd_bus **bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_default.html#>
+    pub fn sd_bus_open(bus: *mut *mut sd_bus) -> c_int;
+    /// `int sd_bus_open_with_description(sd_bus **bus, const char *description);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_default.html#>
+    pub fn sd_bus_open_with_description(bus: *mut *mut sd_bus, description: *const c_char) -> c_int;
+    /// `int sd_bus_open_user(sd_bus **bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_default.html#>
+    pub fn sd_bus_open_user(bus: *mut *mut sd_bus) -> c_int;
+    /// `int sd_bus_open_user_with_description(sd_bus **bus,
+    ///                                 const char *description);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_default.html#>
+    pub fn sd_bus_open_user_with_description(bus: *mut *mut sd_bus, description: *const c_char) -> c_int;
+    /// `int sd_bus_open_user_machine(sd_bus **bus, const char *machine);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_default.html#>
+    #[cfg(systemd_v248)]
+    pub fn sd_bus_open_user_machine(bus: *mut *mut sd_bus, machine: *const c_char) -> c_int;
+    /// `int sd_bus_open_system(sd_bus **bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_default.html#>
+    pub fn sd_bus_open_system(bus: *mut *mut sd_bus) -> c_int;
+    /// `int sd_bus_open_system_with_description(sd_bus **bus,
+    ///                                 const char *description);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_default.html#>
+    pub fn sd_bus_open_system_with_description(bus: *mut *mut sd_bus,
+                                               description: *const c_char)
+                                               -> c_int;
+    /// `int sd_bus_open_system_remote(sd_bus **bus, const char *host);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_default.html#>
+    pub fn sd_bus_open_system_remote(bus: *mut *mut sd_bus, host: *const c_char) -> c_int;
+    /// `int sd_bus_open_system_machine(sd_bus **bus, const char *machine);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_default.html#>
+    pub fn sd_bus_open_system_machine(bus: *mut *mut sd_bus, machine: *const c_char) -> c_int;
+    /// `int sd_bus_new(sd_bus **ret);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_new.html#>
+    pub fn sd_bus_new(bus: *mut *mut sd_bus) -> c_int;
+    /// `int sd_bus_set_address(sd_bus *bus, const char *address);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_set_address.html#>
+    pub fn sd_bus_set_address(bus: *mut sd_bus, address: *const c_char) -> c_int;
+    /// `int sd_bus_set_fd(sd_bus *bus, int input_fd, int output_fd);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_set_fd.html#>
+    pub fn sd_bus_set_fd(bus: *mut sd_bus, input_fd: c_int, output_fd: c_int) -> c_int;
+    /// `int sd_bus_set_server(sd_bus *bus, int b, sd_id128_t bus_id);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_set_server.html#>
+    pub fn sd_bus_set_server(bus: *mut sd_bus, server: c_int, bus_id: sd_id128) -> c_int;
+    /// `int sd_bus_set_bus_client(sd_bus *bus, int b);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_set_server.html#>
+    pub fn sd_bus_set_bus_client(bus: *mut sd_bus, client: c_int) -> c_int;
+    /// `int sd_bus_set_anonymous(sd_bus *bus, int b);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_set_server.html#>
+    pub fn sd_bus_set_anonymous(bus: *mut sd_bus, anonymous: c_int) -> c_int;
+    /// `int sd_bus_set_description(sd_bus *bus, const char *description);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_set_description.html#>
+    pub fn sd_bus_set_description(bus: *mut sd_bus, description: *const c_char) -> c_int;
+    /// `int sd_bus_start(sd_bus *bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_start.html#>
+    pub fn sd_bus_start(bus: *mut sd_bus) -> c_int;
+    /// `int sd_bus_is_open(sd_bus *bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_is_open.html#>
+    pub fn sd_bus_is_open(bus: *mut sd_bus) -> c_int;
+    /// `int sd_bus_flush(sd_bus *bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_close.html#>
+    pub fn sd_bus_flush(bus: *mut sd_bus) -> c_int;
+    /// `void sd_bus_close(sd_bus *bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_close.html#>
+    pub fn sd_bus_close(bus: *mut sd_bus);
+    /// `sd_bus *sd_bus_ref(sd_bus *bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_new.html#>
+    pub fn sd_bus_ref(bus: *mut sd_bus) -> *mut sd_bus;
+    /// `sd_bus *sd_bus_unref(sd_bus *bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_new.html#>
+    pub fn sd_bus_unref(bus: *mut sd_bus) -> *mut sd_bus;
+    /// `sd_bus *sd_bus_close_unref(sd_bus *bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_new.html#>
+    #[cfg(systemd_v241)]
+    pub fn sd_bus_close_unref(bus: *mut sd_bus) -> *mut sd_bus;
+    /// `sd_bus *sd_bus_flush_close_unref(sd_bus *bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_new.html#>
+    pub fn sd_bus_flush_close_unref(bus: *mut sd_bus) -> *mut sd_bus;
+    /// `void sd_bus_default_flush_close(void);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_default_flush_close.html#>
+    pub fn sd_bus_default_flush_close();
+    /// `int sd_bus_get_fd(sd_bus *bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_get_fd.html#>
+    pub fn sd_bus_get_fd(bus: *mut sd_bus) -> c_int;
+    /// `int sd_bus_get_events(sd_bus *bus);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_get_fd.html#>
+    pub fn sd_bus_get_events(bus: *mut sd_bus) -> c_int;
+    /// `int sd_bus_get_timeout(sd_bus *bus, uint64_t *timeout_usec);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_get_fd.html#>
+    pub fn sd_bus_get_timeout(bus: *mut sd_bus, timeout: *mut u64) -> c_int;
+    /// `int sd_bus_process(sd_bus *bus, sd_bus_message **r);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_process.html#>
+    pub fn sd_bus_process(bus: *mut sd_bus, message: *mut *mut sd_bus_message) -> c_int;
+    /// `int sd_bus_wait(sd_bus *bus, uint64_t timeout_usec);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_wait.html#>
+    pub fn sd_bus_wait(bus: *mut sd_bus, timeout: u64) -> c_int;
+    /// `int sd_bus_request_name(sd_bus *bus, const char *name, uint64_t flags);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_request_name.html#>
+    pub fn sd_bus_request_name(bus: *mut sd_bus, name: *const c_char, flags: u64) -> c_int;
+    /// `int sd_bus_release_name(sd_bus *bus, const char *name);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_request_name.html#>
+    pub fn sd_bus_release_name(bus: *mut sd_bus, name: *const c_char) -> c_int;
+    /// `int sd_bus_call(sd_bus *bus, sd_bus_message *m, uint64_t usec,
+    ///                 sd_bus_error *ret_error, sd_bus_message **reply);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_call.html#>
+    pub fn sd_bus_call(bus: *mut sd_bus,
+                       message: *mut sd_bus_message,
+                       timeout: u64,
+                       error: *mut sd_bus_error,
+                       reply: *mut *mut sd_bus_message)
+                       -> c_int;
+    /// `int sd_bus_call_async(sd_bus *bus, sd_bus_slot **slot,
+    ///             sd_bus_message *m, sd_bus_message_handler_t callback,
+    ///             void *userdata, uint64_t usec);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_call.html#>
+    pub fn sd_bus_call_async(bus: *mut sd_bus,
+                             slot: *mut *mut sd_bus_slot,
+                             message: *mut sd_bus_message,
+                             callback: sd_bus_message_handler_t,
+                             userdata: *mut c_void,
+                             timeout: u64)
+                             -> c_int;
+    /// `int sd_bus_add_match(sd_bus *bus, sd_bus_slot **slot,
+    ///             const char *match, sd_bus_message_handler_t callback,
+    ///             void *userdata);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_add_match.html#>
+    pub fn sd_bus_add_match(bus: *mut sd_bus,
+                            slot: *mut *mut sd_bus_slot,
+                            rule: *const c_char,
+                            callback: sd_bus_message_handler_t,
+                            userdata: *mut c_void)
+                            -> c_int;
+    /// `int sd_bus_add_match_async(sd_bus *bus, sd_bus_slot **slot,
+    ///             const char *match, sd_bus_message_handler_t callback,
+    ///             sd_bus_message_handler_t install_callback, void *userdata);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_add_match.html#>
+    pub fn sd_bus_add_match_async(bus: *mut sd_bus,
+                                  slot: *mut *mut sd_bus_slot,
+                                  rule: *const c_char,
+                                  callback: sd_bus_message_handler_t,
+                                  install_callback: sd_bus_message_handler_t,
+                                  userdata: *mut c_void)
+                                  -> c_int;
+    /// `int sd_bus_match_signal(sd_bus *bus, sd_bus_slot **ret,
+    ///             const char *sender, const char *path, const char *interface,
+    ///             const char *member, sd_bus_message_handler_t callback,
+    ///             void *userdata);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_add_match.html#>
+    pub fn sd_bus_match_signal(bus: *mut sd_bus,
+                               slot: *mut *mut sd_bus_slot,
+                               sender: *const c_char,
+                               path: *const c_char,
+                               interface: *const c_char,
+                               member: *const c_char,
+                               callback: sd_bus_message_handler_t,
+                               userdata: *mut c_void)
+                               -> c_int;
+    /// `int sd_bus_match_signal_async(sd_bus *bus, sd_bus_slot **ret,
+    ///             const char *sender, const char *path, const char *interface,
+    ///             const char *member, sd_bus_message_handler_t callback,
+    ///             sd_bus_message_handler_t install_callback, void *userdata);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_add_match.html#>
+    pub fn sd_bus_match_signal_async(bus: *mut sd_bus,
+                                     slot: *mut *mut sd_bus_slot,
+                                     sender: *const c_char,
+                                     path: *const c_char,
+                                     interface: *const c_char,
+                                     member: *const c_char,
+                                     callback: sd_bus_message_handler_t,
+                                     install_callback: sd_bus_message_handler_t,
+                                     userdata: *mut c_void)
+                                     -> c_int;
+    /// `sd_bus_slot *sd_bus_slot_ref(sd_bus_slot *slot);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_slot_ref.html#>
+    pub fn sd_bus_slot_ref(slot: *mut sd_bus_slot) -> *mut sd_bus_slot;
+    /// `sd_bus_slot *sd_bus_slot_unref(sd_bus_slot *slot);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_slot_ref.html#>
+    pub fn sd_bus_slot_unref(slot: *mut sd_bus_slot) -> *mut sd_bus_slot;
+    /// `void *sd_bus_slot_get_userdata(sd_bus_slot *slot);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_slot_set_userdata.html#>
+    pub fn sd_bus_slot_get_userdata(slot: *mut sd_bus_slot) -> *mut c_void;
+    /// `void *sd_bus_slot_set_userdata(sd_bus_slot *slot, void *userdata);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_slot_set_userdata.html#>
+    pub fn sd_bus_slot_set_userdata(slot: *mut sd_bus_slot, userdata: *mut c_void) -> *mut c_void;
+    /// `int sd_bus_slot_set_description(sd_bus_slot *slot,
+    ///                                 const char *description);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_slot_set_description.html#>
+    pub fn sd_bus_slot_set_description(slot: *mut sd_bus_slot, description: *const c_char) -> c_int;
+    /// `int sd_bus_slot_get_description(sd_bus_slot *slot,
+    ///                                 const char **description);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_slot_set_description.html#>
+    pub fn sd_bus_slot_get_description(slot: *mut sd_bus_slot, description: *mut *const c_char) -> c_int;
+    /// `int sd_bus_slot_set_floating(sd_bus_slot *slot, int b);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_slot_set_floating.html#>
+    pub fn sd_bus_slot_set_floating(slot: *mut sd_bus_slot, floating: c_int) -> c_int;
+    /// `int sd_bus_slot_get_floating(sd_bus_slot *slot);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_slot_set_floating.html#>
+    pub fn sd_bus_slot_get_floating(slot: *mut sd_bus_slot) -> c_int;
+    /// `int sd_bus_slot_set_destroy_callback(sd_bus_slot *s,
+    ///                                 sd_bus_destroy_t callback);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_slot_set_destroy_callback.html#>
+    pub fn sd_bus_slot_set_destroy_callback(slot: *mut sd_bus_slot, callback: sd_bus_destroy_t) -> c_int;
+    /// `int sd_bus_slot_get_destroy_callback(sd_bus_slot *s,
+    ///                                 sd_bus_destroy_t *callback);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_slot_set_destroy_callback.html#>
+    pub fn sd_bus_slot_get_destroy_callback(slot: *mut sd_bus_slot, callback: *mut sd_bus_destroy_t) -> c_int;
+    /// `int sd_bus_reply_method_error(sd_bus_message *call,
+    ///                                 const sd_bus_error *e);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_reply_method_error.html#>
+    pub fn sd_bus_reply_method_error(call: *mut sd_bus_message, error: *const sd_bus_error) -> c_int;
+    /// `int sd_bus_reply_method_errno(sd_bus_message *call, int error,
+    ///                                 const sd_bus_error *e);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_reply_method_error.html#>
+    pub fn sd_bus_reply_method_errno(call: *mut sd_bus_message,
+                                     errno: c_int,
+                                     error: *const sd_bus_error)
+                                     -> c_int;
+    /// `sd_bus_message *sd_bus_message_ref(sd_bus_message *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_new.html#>
+    pub fn sd_bus_message_ref(message: *mut sd_bus_message) -> *mut sd_bus_message;
+    /// `sd_bus_message *sd_bus_message_unref(sd_bus_message *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_new.html#>
+    pub fn sd_bus_message_unref(message: *mut sd_bus_message) -> *mut sd_bus_message;
+    /// `int sd_bus_message_new_method_call(sd_bus *bus, sd_bus_message **m,
+    ///             const char *destination, const char *path,
+    ///             const char *interface, const char *member);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_new_method_call.html#>
+    pub fn sd_bus_message_new_method_call(bus: *mut sd_bus,
+                                          message: *mut *mut sd_bus_message,
+                                          destination: *const c_char,
+                                          path: *const c_char,
+                                          interface: *const c_char,
+                                          member: *const c_char)
+                                          -> c_int;
+    /// `int sd_bus_message_new_method_return(sd_bus_message *call,
+    ///                                 sd_bus_message **m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_new_method_call.html#>
+    pub fn sd_bus_message_new_method_return(call: *mut sd_bus_message,
+                                            message: *mut *mut sd_bus_message)
+                                            -> c_int;
+    /// `int sd_bus_message_new_signal(sd_bus *bus, sd_bus_message **m,
+    ///             const char *path, const char *interface, const char *member);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_new_signal.html#>
+    pub fn sd_bus_message_new_signal(bus: *mut sd_bus,
+                                     message: *mut *mut sd_bus_message,
+                                     path: *const c_char,
+                                     interface: *const c_char,
+                                     member: *const c_char)
+                                     -> c_int;
+    /// `int sd_bus_send(sd_bus *bus, sd_bus_message *m, uint64_t *cookie);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_send.html#>
+    pub fn sd_bus_send(bus: *mut sd_bus, message: *mut sd_bus_message, cookie: *mut u64) -> c_int;
+    /// `int sd_bus_message_get_type(sd_bus_message *m, uint8_t *type);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_get_type.html#>
+    pub fn sd_bus_message_get_type(message: *mut sd_bus_message, kind: *mut u8) -> c_int;
+    /// `int sd_bus_message_is_signal(sd_bus_message *m, const char *interface,
+    ///                                 const char *member);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_get_type.html#>
+    pub fn sd_bus_message_is_signal(message: *mut sd_bus_message,
+                                    interface: *const c_char,
+                                    member: *const c_char)
+                                    -> c_int;
+    /// `int sd_bus_message_is_method_call(sd_bus_message *m,
+    ///                     const char *interface, const char *member);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_get_type.html#>
+    pub fn sd_bus_message_is_method_call(message: *mut sd_bus_message,
+                                         interface: *const c_char,
+                                         member: *const c_char)
+                                         -> c_int;
+    /// `int sd_bus_message_is_method_error(sd_bus_message *m, const char *name);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_get_type.html#>
+    pub fn sd_bus_message_is_method_error(message: *mut sd_bus_message, name: *const c_char) -> c_int;
+    /// `const sd_bus_error *sd_bus_message_get_error(sd_bus_message *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd-bus.html#>
+    pub fn sd_bus_message_get_error(message: *mut sd_bus_message) -> *const sd_bus_error;
+    /// `int sd_bus_message_get_errno(sd_bus_message *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd-bus.html#>
+    pub fn sd_bus_message_get_errno(message: *mut sd_bus_message) -> c_int;
+    /// `const char *sd_bus_message_get_signature(sd_bus_message *m, int complete);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_get_signature.html#>
+    pub fn sd_bus_message_get_signature(message: *mut sd_bus_message, complete: c_int) -> *const c_char;
+    /// `const char *sd_bus_message_get_path(sd_bus_message *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_set_destination.html#>
+    pub fn sd_bus_message_get_path(message: *mut sd_bus_message) -> *const c_char;
+    /// `const char *sd_bus_message_get_interface(sd_bus_message *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_set_destination.html#>
+    pub fn sd_bus_message_get_interface(message: *mut sd_bus_message) -> *const c_char;
+    /// `const char *sd_bus_message_get_member(sd_bus_message *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_set_destination.html#>
+    pub fn sd_bus_message_get_member(message: *mut sd_bus_message) -> *const c_char;
+    /// `const char *sd_bus_message_get_destination(sd_bus_message *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_set_destination.html#>
+    pub fn sd_bus_message_get_destination(message: *mut sd_bus_message) -> *const c_char;
+    /// `const char *sd_bus_message_get_sender(sd_bus_message *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_set_destination.html#>
+    pub fn sd_bus_message_get_sender(message: *mut sd_bus_message) -> *const c_char;
+    /// `int sd_bus_message_append_basic(sd_bus_message *m, char type,
+    ///                                 const void *p);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_append_basic.html#>
+    pub fn sd_bus_message_append_basic(message: *mut sd_bus_message,
+                                       kind: c_char,
+                                       value: *const c_void)
+                                       -> c_int;
+    /// `int sd_bus_message_append_array(sd_bus_message *m, char type,
+    ///                                 const void *ptr, size_t size);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_append_array.html#>
+    pub fn sd_bus_message_append_array(message: *mut sd_bus_message,
+                                       kind: c_char,
+                                       array: *const c_void,
+                                       size: size_t)
+                                       -> c_int;
+    /// `int sd_bus_message_append_strv(sd_bus_message *m, char **l);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_append_strv.html#>
+    pub fn sd_bus_message_append_strv(message: *mut sd_bus_message, strv: *mut *mut c_char) -> c_int;
+    /// `int sd_bus_message_open_container(sd_bus_message *m, char type,
+    ///                                 const char *contents);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_open_container.html#>
+    pub fn sd_bus_message_open_container(message: *mut sd_bus_message,
+                                         kind: c_char,
+                                         contents: *const c_char)
+                                         -> c_int;
+    /// `int sd_bus_message_close_container(sd_bus_message *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_open_container.html#>
+    pub fn sd_bus_message_close_container(message: *mut sd_bus_message) -> c_int;
+    /// `int sd_bus_message_enter_container(sd_bus_message *m, char type,
+    ///                                 const char *contents);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_open_container.html#>
+    pub fn sd_bus_message_enter_container(message: *mut sd_bus_message,
+                                          kind: c_char,
+                                          contents: *const c_char)
+                                          -> c_int;
+    /// `int sd_bus_message_exit_container(sd_bus_message *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_open_container.html#>
+    pub fn sd_bus_message_exit_container(message: *mut sd_bus_message) -> c_int;
+    /// `int sd_bus_message_read_basic(sd_bus_message *m, char type, void *p);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_read_basic.html#>
+    pub fn sd_bus_message_read_basic(message: *mut sd_bus_message, kind: c_char, value: *mut c_void) -> c_int;
+    /// `int sd_bus_message_read_array(sd_bus_message *m, char type,
+    ///                                 const void **ptr, size_t *size);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_read_array.html#>
+    pub fn sd_bus_message_read_array(message: *mut sd_bus_message,
+                                     kind: c_char,
+                                     array: *mut *const c_void,
+                                     size: *mut size_t)
+                                     -> c_int;
+    /// `int sd_bus_message_read_strv(sd_bus_message *m, char ***l);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_read_strv.html#>
+    pub fn sd_bus_message_read_strv(message: *mut sd_bus_message, strv: *mut *mut *mut c_char) -> c_int;
+    /// `int sd_bus_message_peek_type(sd_bus_message *m, char *type,
+    ///                                 const char **contents);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_peek_type.html#>
+    pub fn sd_bus_message_peek_type(message: *mut sd_bus_message,
+                                    kind: *mut c_char,
+                                    contents: *mut *const c_char)
+                                    -> c_int;
+    /// `int sd_bus_message_verify_type(sd_bus_message *m, char type,
+    ///                                 const char *contents);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_peek_type.html#>
+    pub fn sd_bus_message_verify_type(message: *mut sd_bus_message,
+                                      kind: c_char,
+                                      contents: *const c_char)
+                                      -> c_int;
+    /// `int sd_bus_message_skip(sd_bus_message *m, const char *types);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_skip.html#>
+    pub fn sd_bus_message_skip(message: *mut sd_bus_message, types: *const c_char) -> c_int;
+    /// `int sd_bus_message_at_end(sd_bus_message *m, int complete);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_at_end.html#>
+    pub fn sd_bus_message_at_end(message: *mut sd_bus_message, complete: c_int) -> c_int;
+    /// `int sd_bus_message_rewind(sd_bus_message *m, int complete);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_rewind.html#>
+    pub fn sd_bus_message_rewind(message: *mut sd_bus_message, complete: c_int) -> c_int;
+    /// `int sd_bus_message_dump(sd_bus_message *m, FILE *f, uint64_t flags);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_dump.html#>
+    #[cfg(systemd_v245)]
+    pub fn sd_bus_message_dump(message: *mut sd_bus_message, file: *mut libc::FILE, flags: u64) -> c_int;
+    /// `int sd_bus_add_object(sd_bus *bus, sd_bus_slot **slot, const char *path,
+    ///             sd_bus_message_handler_t callback, void *userdata);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_add_object.html#>
+    pub fn sd_bus_add_object(bus: *mut sd_bus,
+                             slot: *mut *mut sd_bus_slot,
+                             path: *const c_char,
+                             callback: sd_bus_message_handler_t,
+                             userdata: *mut c_void)
+                             -> c_int;
+    /// `int sd_bus_add_object_vtable(sd_bus *bus, sd_bus_slot **slot,
+    ///             const char *path, const char *interface,
+    ///             const sd_bus_vtable *vtable, void *userdata);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_add_object.html#>
+    pub fn sd_bus_add_object_vtable(bus: *mut sd_bus,
+                                    slot: *mut *mut sd_bus_slot,
+                                    path: *const c_char,
+                                    interface: *const c_char,
+                                    vtable: *const sd_bus_vtable,
+                                    userdata: *mut c_void)
+                                    -> c_int;
+    /// `int sd_bus_add_fallback_vtable(sd_bus *bus, sd_bus_slot **slot,
+    ///             const char *prefix, const char *interface,
+    ///             const sd_bus_vtable *vtable, sd_bus_object_find_t find,
+    ///             void *userdata);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_add_object.html#>
+    pub fn sd_bus_add_fallback_vtable(bus: *mut sd_bus,
+                                      slot: *mut *mut sd_bus_slot,
+                                      prefix: *const c_char,
+                                      interface: *const c_char,
+                                      vtable: *const sd_bus_vtable,
+                                      find: sd_bus_object_find_t,
+                                      userdata: *mut c_void)
+                                      -> c_int;
+    /// `int sd_bus_add_node_enumerator(sd_bus *bus, sd_bus_slot **slot,
+    ///             const char *path, sd_bus_node_enumerator_t callback,
+    ///             void *userdata);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_add_node_enumerator.html#>
+    pub fn sd_bus_add_node_enumerator(bus: *mut sd_bus,
+                                      slot: *mut *mut sd_bus_slot,
+                                      path: *const c_char,
+                                      callback: sd_bus_node_enumerator_t,
+                                      userdata: *mut c_void)
+                                      -> c_int;
+    /// `int sd_bus_add_object_manager(sd_bus *bus, sd_bus_slot **slot,
+    ///                                 const char *path);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_add_object_manager.html#>
+    pub fn sd_bus_add_object_manager(bus: *mut sd_bus, slot: *mut *mut sd_bus_slot, path: *const c_char) -> c_int;
+    /// `int sd_bus_emit_properties_changed_strv(sd_bus *bus, const char *path,
+    ///             const char *interface, char **names);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_emit_signal.html#>
+    pub fn sd_bus_emit_properties_changed_strv(bus: *mut sd_bus,
+                                               path: *const c_char,
+                                               interface: *const c_char,
+                                               names: *mut *mut c_char)
+                                               -> c_int;
+    /// `int sd_bus_emit_object_added(sd_bus *bus, const char *path);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_emit_signal.html#>
+    pub fn sd_bus_emit_object_added(bus: *mut sd_bus, path: *const c_char) -> c_int;
+    /// `int sd_bus_emit_object_removed(sd_bus *bus, const char *path);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_emit_signal.html#>
+    pub fn sd_bus_emit_object_removed(bus: *mut sd_bus, path: *const c_char) -> c_int;
+    /// `int sd_bus_emit_interfaces_added_strv(sd_bus *bus, const char *path,
+    ///                                 char **interfaces);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_emit_signal.html#>
+    pub fn sd_bus_emit_interfaces_added_strv(bus: *mut sd_bus,
+                                             path: *const c_char,
+                                             interfaces: *mut *mut c_char)
+                                             -> c_int;
+    /// `int sd_bus_emit_interfaces_removed_strv(sd_bus *bus, const char *path,
+    ///                                 char **interfaces);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_emit_signal.html#>
+    pub fn sd_bus_emit_interfaces_removed_strv(bus: *mut sd_bus,
+                                               path: *const c_char,
+                                               interfaces: *mut *mut c_char)
+                                               -> c_int;
+    /// `void sd_bus_error_free(sd_bus_error *e);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_error.html#>
+    pub fn sd_bus_error_free(error: *mut sd_bus_error);
+    /// `int sd_bus_error_set(sd_bus_error *e, const char *name,
+    ///                                 const char *message);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_error.html#>
+    pub fn sd_bus_error_set(error: *mut sd_bus_error, name: *const c_char, message: *const c_char) -> c_int;
+    /// `int sd_bus_error_set_const(sd_bus_error *e, const char *name,
+    ///                                 const char *message);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_error.html#>
+    pub fn sd_bus_error_set_const(error: *mut sd_bus_error,
+                                  name: *const c_char,
+                                  message: *const c_char)
+                                  -> c_int;
+    /// `int sd_bus_error_set_errno(sd_bus_error *e, int error);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_error.html#>
+    pub fn sd_bus_error_set_errno(error: *mut sd_bus_error, errno: c_int) -> c_int;
+    /// `int sd_bus_error_get_errno(const sd_bus_error *e);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_error.html#>
+    pub fn sd_bus_error_get_errno(error: *const sd_bus_error) -> c_int;
+    /// `int sd_bus_error_copy(sd_bus_error *dst, const sd_bus_error *e);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_error.html#>
+    pub fn sd_bus_error_copy(destination: *mut sd_bus_error, error: *const sd_bus_error) -> c_int;
+    /// `int sd_bus_error_move(sd_bus_error *dst, sd_bus_error *e);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_error.html#>
+    #[cfg(systemd_v240)]
+    pub fn sd_bus_error_move(destination: *mut sd_bus_error, error: *mut sd_bus_error) -> c_int;
+    /// `int sd_bus_error_is_set(const sd_bus_error *e);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_error.html#>
+    pub fn sd_bus_error_is_set(error: *const sd_bus_error) -> c_int;
+    /// `int sd_bus_error_has_name(const sd_bus_error *e, const char *name);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_error.html#>
+    pub fn sd_bus_error_has_name(error: *const sd_bus_error, name: *const c_char) -> c_int;
+}
+
+// bindings taking a `va_list` require the unstable `c_variadic` language
+// feature and are therefore only available on nightly with feature `va_list`
+#[cfg(feature = "va_list")]
+sd_extern! {
+    /// `int sd_bus_call_methodv(sd_bus *bus, const char *destination,
+    ///             const char *path, const char *interface, const char *member,
+    ///             sd_bus_error *ret_error, sd_bus_message **reply,
+    ///             const char *types, va_list ap);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_call_method.html#>
+    #[cfg(systemd_v246)]
+    pub fn sd_bus_call_methodv(bus: *mut sd_bus,
+                               destination: *const c_char,
+                               path: *const c_char,
+                               interface: *const c_char,
+                               member: *const c_char,
+                               error: *mut sd_bus_error,
+                               reply: *mut *mut sd_bus_message,
+                               types: *const c_char,
+                               args: VaList)
+                               -> c_int;
+    /// `int sd_bus_message_appendv(sd_bus_message *m, const char *types,
+    ///                                 va_list ap);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_append.html#>
+    pub fn sd_bus_message_appendv(message: *mut sd_bus_message, types: *const c_char, args: VaList) -> c_int;
+    /// `int sd_bus_message_readv(sd_bus_message *m, const char *types,
+    ///                                 va_list ap);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_message_read.html#>
+    #[cfg(systemd_v240)]
+    pub fn sd_bus_message_readv(message: *mut sd_bus_message, types: *const c_char, args: VaList) -> c_int;
+    /// `int sd_bus_call_method_asyncv(sd_bus *bus, sd_bus_slot **slot,
+    ///             const char *destination, const char *path,
+    ///             const char *interface, const char *member,
+    ///             sd_bus_message_handler_t callback, void *userdata,
+    ///             const char *types, va_list ap);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_call_method.html#>
+    #[cfg(systemd_v246)]
+    pub fn sd_bus_call_method_asyncv(bus: *mut sd_bus,
+                                     slot: *mut *mut sd_bus_slot,
+                                     destination: *const c_char,
+                                     path: *const c_char,
+                                     interface: *const c_char,
+                                     member: *const c_char,
+                                     callback: sd_bus_message_handler_t,
+                                     userdata: *mut c_void,
+                                     types: *const c_char,
+                                     args: VaList)
+                                     -> c_int;
+    /// `int sd_bus_emit_signalv(sd_bus *bus, const char *path,
+    ///             const char *interface, const char *member,
+    ///             const char *types, va_list ap);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_emit_signal.html#>
+    #[cfg(systemd_v246)]
+    pub fn sd_bus_emit_signalv(bus: *mut sd_bus,
+                               path: *const c_char,
+                               interface: *const c_char,
+                               member: *const c_char,
+                               types: *const c_char,
+                               args: VaList)
+                               -> c_int;
+    /// `int sd_bus_error_setfv(sd_bus_error *e, const char *name,
+    ///                                 const char *format, va_list ap);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_bus_error.html#>
+    #[cfg(systemd_v252)]
+    pub fn sd_bus_error_setfv(error: *mut sd_bus_error,
+                              name: *const c_char,
+                              format: *const c_char,
+                              args: VaList)
+                              -> c_int;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{ffi::CStr,
+              sync::{atomic::{AtomicUsize, Ordering},
+                     Arc},
+              thread::{self, JoinHandle}};
+
+    macro_rules! c {
+        ($string:literal) => {
+            concat!($string, "\0").as_ptr() as *const c_char
+        };
+    }
+
+    struct Server(*mut sd_bus);
+
+    unsafe impl Send for Server {}
+
+    // Connects a client to a private server over a socketpair. `setup` exports
+    // the objects of the server, a thread processes its messages until the
+    // client disconnects.
+    unsafe fn connect(setup: impl FnOnce(*mut sd_bus)) -> (*mut sd_bus, JoinHandle<()>) {
+        let mut pair = [-1; 2];
+        assert_eq!(libc::socketpair(libc::AF_UNIX,
+                                    libc::SOCK_STREAM | libc::SOCK_CLOEXEC,
+                                    0,
+                                    pair.as_mut_ptr()),
+                   0);
+        let mut client = ptr::null_mut();
+        let mut server = ptr::null_mut();
+        assert_eq!(sd_bus_new(&mut client), 0);
+        assert_eq!(sd_bus_new(&mut server), 0);
+        assert_eq!(sd_bus_set_fd(client, pair[0], pair[0]), 0);
+        assert_eq!(sd_bus_set_fd(server, pair[1], pair[1]), 0);
+        assert_eq!(sd_bus_set_server(server, 1, sd_id128::from_bytes([7; 16])),
+                   0);
+        assert_eq!(sd_bus_set_anonymous(client, 1), 0);
+        assert_eq!(sd_bus_set_anonymous(server, 1), 0);
+        setup(server);
+        assert!(sd_bus_start(server) >= 0);
+        assert!(sd_bus_start(client) >= 0);
+        let server = Server(server);
+        let thread = thread::spawn(move || {
+            loop {
+                let result = sd_bus_process(server.0, ptr::null_mut());
+                if result < 0 || result == 0 && sd_bus_wait(server.0, 5_000_000) <= 0 {
+                    break;
+                }
+            }
+            sd_bus_flush_close_unref(server.0);
+        });
+        (client, thread)
+    }
+
+    unsafe fn disconnect(client: *mut sd_bus, thread: JoinHandle<()>) {
+        sd_bus_flush_close_unref(client);
+        thread.join().unwrap();
+    }
+
+    // replies to `Double(u) -> u` and fails for 0
+    unsafe extern "C" fn double(message: *mut sd_bus_message,
+                                _userdata: *mut c_void,
+                                _error: *mut sd_bus_error)
+                                -> c_int {
+        if sd_bus_message_is_method_call(message, c!("org.example.Test"), c!("Double")) <= 0 {
+            return 0;
+        }
+        let mut value = 0u32;
+        let result = sd_bus_message_read_basic(message,
+                                               SD_BUS_TYPE_UINT32,
+                                               &mut value as *mut u32 as *mut c_void);
+        if result < 0 {
+            return result;
+        }
+        if value == 0 {
+            let mut error = SD_BUS_ERROR_NULL;
+            sd_bus_error_set_const(&mut error, c!("org.example.Zero"), c!("nothing to double"));
+            return sd_bus_reply_method_error(message, &error);
+        }
+        let mut reply = ptr::null_mut();
+        let result = sd_bus_message_new_method_return(message, &mut reply);
+        if result < 0 {
+            return result;
+        }
+        let value = value * 2;
+        let mut result = sd_bus_message_append_basic(reply,
+                                                     SD_BUS_TYPE_UINT32,
+                                                     &value as *const u32 as *const c_void);
+        if result >= 0 {
+            result = sd_bus_send(ptr::null_mut(), reply, ptr::null_mut());
+        }
+        sd_bus_message_unref(reply);
+        result.min(1)
+    }
+
+    unsafe fn export_double(server: *mut sd_bus) {
+        assert!(sd_bus_add_object(server,
+                                  ptr::null_mut(),
+                                  c!("/org/example/test"),
+                                  Some(double),
+                                  ptr::null_mut())
+                >= 0);
+    }
+
+    unsafe fn call_double(client: *mut sd_bus,
+                          value: u32,
+                          error: &mut sd_bus_error)
+                          -> Result<u32, c_int> {
+        let mut call = ptr::null_mut();
+        assert_eq!(sd_bus_message_new_method_call(client,
+                                                  &mut call,
+                                                  ptr::null(),
+                                                  c!("/org/example/test"),
+                                                  c!("org.example.Test"),
+                                                  c!("Double")),
+                   0);
+        assert!(sd_bus_message_append_basic(call,
+                                            SD_BUS_TYPE_UINT32,
+                                            &value as *const u32 as *const c_void)
+                >= 0);
+        let mut reply = ptr::null_mut();
+        let result = sd_bus_call(client, call, 0, error, &mut reply);
+        sd_bus_message_unref(call);
+        if result < 0 {
+            assert!(reply.is_null());
+            return Err(result);
+        }
+        let mut value = 0u32;
+        assert!(sd_bus_message_read_basic(reply,
+                                          SD_BUS_TYPE_UINT32,
+                                          &mut value as *mut u32 as *mut c_void)
+                > 0);
+        sd_bus_message_unref(reply);
+        Ok(value)
+    }
+
+    #[test]
+    fn call() {
+        unsafe {
+            let (client, thread) = connect(|server| export_double(server));
+            let mut error = SD_BUS_ERROR_NULL;
+            assert_eq!(call_double(client, 21, &mut error), Ok(42));
+            assert_eq!(sd_bus_error_is_set(&error), 0);
+            disconnect(client, thread);
+        }
+    }
+
+    #[test]
+    fn call_error() {
+        unsafe {
+            let (client, thread) = connect(|server| export_double(server));
+            let mut error = SD_BUS_ERROR_NULL;
+            assert!(call_double(client, 0, &mut error).is_err());
+            assert_ne!(sd_bus_error_is_set(&error), 0);
+            assert_ne!(sd_bus_error_has_name(&error, c!("org.example.Zero")), 0);
+            assert_eq!(CStr::from_ptr(error.message).to_bytes(),
+                       b"nothing to double");
+            sd_bus_error_free(&mut error);
+            assert!(error.name.is_null());
+            assert!(error.message.is_null());
+            assert_eq!(sd_bus_error_is_set(&error), 0);
+            disconnect(client, thread);
+        }
+    }
+
+    #[cfg(not(feature = "dlopen"))]
+    #[test]
+    fn call_method() {
+        unsafe {
+            let (client, thread) = connect(|server| export_double(server));
+            let mut error = SD_BUS_ERROR_NULL;
+            let mut reply = ptr::null_mut();
+            assert!(sd_bus_call_method(client,
+                                       ptr::null(),
+                                       c!("/org/example/test"),
+                                       c!("org.example.Test"),
+                                       c!("Double"),
+                                       &mut error,
+                                       &mut reply,
+                                       c!("u"),
+                                       4u32)
+                    > 0);
+            let mut value = 0u32;
+            assert!(sd_bus_message_read(reply, c!("u"), &mut value as *mut u32) > 0);
+            assert_eq!(value, 8);
+            sd_bus_message_unref(reply);
+            sd_bus_error_free(&mut error);
+            disconnect(client, thread);
+        }
+    }
+
+    static COUNT: u32 = 3;
+
+    static VTABLE: [sd_bus_vtable; 5] =
+        [sd_bus_vtable::start(0),
+         sd_bus_vtable::method_with_names(c!("Double"),
+                                          c!("u"),
+                                          c!("u"),
+                                          c!("value\0doubled\0"),
+                                          Some(double),
+                                          0,
+                                          SD_BUS_VTABLE_UNPRIVILEGED),
+         sd_bus_vtable::property(c!("Count"), c!("u"), None, 0, SD_BUS_VTABLE_PROPERTY_CONST),
+         sd_bus_vtable::signal_with_names(c!("Doubled"), c!("u"), c!("value\0"), 0),
+         sd_bus_vtable::end()];
+
+    // the names lack the one of the result
+    static MISMATCHED: [sd_bus_vtable; 3] = [sd_bus_vtable::start(0),
+                                             sd_bus_vtable::method_with_names(c!("Double"),
+                                                                              c!("u"),
+                                                                              c!("u"),
+                                                                              c!("value\0"),
+                                                                              Some(double),
+                                                                              0,
+                                                                              0),
+                                             sd_bus_vtable::end()];
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn vtable_size() {
+        assert_eq!(mem::size_of::<sd_bus_vtable>(), 56);
+    }
+
+    #[test]
+    fn vtable_kind_and_flags() {
+        let flags = SD_BUS_VTABLE_DEPRECATED | SD_BUS_VTABLE_HIDDEN | SD_BUS_VTABLE_CAPABILITY(12);
+        let entries =
+            [(sd_bus_vtable::start(flags), _SD_BUS_VTABLE_START),
+             (sd_bus_vtable::method(c!("M"), c!(""), c!(""), None, flags), _SD_BUS_VTABLE_METHOD),
+             (sd_bus_vtable::method_with_names(c!("M"), c!(""), c!(""), c!(""), None, 0, flags),
+              _SD_BUS_VTABLE_METHOD),
+             (sd_bus_vtable::signal(c!("S"), c!(""), flags), _SD_BUS_VTABLE_SIGNAL),
+             (sd_bus_vtable::signal_with_names(c!("S"), c!(""), c!(""), flags),
+              _SD_BUS_VTABLE_SIGNAL),
+             (sd_bus_vtable::property(c!("P"), c!("u"), None, 0, flags), _SD_BUS_VTABLE_PROPERTY),
+             (sd_bus_vtable::writable_property(c!("W"), c!("u"), None, None, 0, flags),
+              _SD_BUS_VTABLE_WRITABLE_PROPERTY)];
+        for (entry, kind) in &entries {
+            assert_eq!(entry.kind(), *kind);
+            assert_eq!(entry.flags(), flags);
+        }
+        let end = sd_bus_vtable::end();
+        assert_eq!(end.kind(), _SD_BUS_VTABLE_END);
+        assert_eq!(end.flags(), 0);
+    }
+
+    #[test]
+    fn add_object_vtable() {
+        unsafe {
+            let mut bus = ptr::null_mut();
+            assert_eq!(sd_bus_new(&mut bus), 0);
+            let userdata = &COUNT as *const u32 as *mut c_void;
+            assert_eq!(sd_bus_add_object_vtable(bus,
+                                                ptr::null_mut(),
+                                                c!("/org/example/test"),
+                                                c!("org.example.Test"),
+                                                MISMATCHED.as_ptr(),
+                                                userdata),
+                       -libc::EINVAL);
+            assert_eq!(sd_bus_add_object_vtable(bus,
+                                                ptr::null_mut(),
+                                                c!("/org/example/test"),
+                                                c!("org.example.Test"),
+                                                VTABLE.as_ptr(),
+                                                userdata),
+                       0);
+            sd_bus_unref(bus);
+        }
+    }
+
+    #[test]
+    fn call_object_vtable() {
+        unsafe {
+            let (client, thread) = connect(|server| {
+                assert_eq!(sd_bus_add_object_vtable(server,
+                                                    ptr::null_mut(),
+                                                    c!("/org/example/test"),
+                                                    c!("org.example.Test"),
+                                                    VTABLE.as_ptr(),
+                                                    &COUNT as *const u32 as *mut c_void),
+                           0)
+            });
+            let mut error = SD_BUS_ERROR_NULL;
+            assert_eq!(call_double(client, 5, &mut error), Ok(10));
+            disconnect(client, thread);
+        }
+    }
+
+    // emits `Doubled(7)` on the bus passed as userdata before it replies to
+    // `Emit()`
+    unsafe extern "C" fn emit(message: *mut sd_bus_message,
+                              userdata: *mut c_void,
+                              _error: *mut sd_bus_error)
+                              -> c_int {
+        if sd_bus_message_is_method_call(message, c!("org.example.Test"), c!("Emit")) <= 0 {
+            return 0;
+        }
+        let mut signal = ptr::null_mut();
+        let result = sd_bus_message_new_signal(userdata as *mut sd_bus,
+                                               &mut signal,
+                                               c!("/org/example/test"),
+                                               c!("org.example.Test"),
+                                               c!("Doubled"));
+        if result < 0 {
+            return result;
+        }
+        let value = 7u32;
+        let mut result = sd_bus_message_append_basic(signal,
+                                                     SD_BUS_TYPE_UINT32,
+                                                     &value as *const u32 as *const c_void);
+        if result >= 0 {
+            result = sd_bus_send(ptr::null_mut(), signal, ptr::null_mut());
+        }
+        sd_bus_message_unref(signal);
+        if result < 0 {
+            return result;
+        }
+        let mut reply = ptr::null_mut();
+        let mut result = sd_bus_message_new_method_return(message, &mut reply);
+        if result >= 0 {
+            result = sd_bus_send(ptr::null_mut(), reply, ptr::null_mut());
+        }
+        sd_bus_message_unref(reply);
+        result.min(1)
+    }
+
+    unsafe fn export_emit(server: *mut sd_bus) {
+        assert!(sd_bus_add_object(server,
+                                  ptr::null_mut(),
+                                  c!("/org/example/test"),
+                                  Some(emit),
+                                  server as *mut c_void)
+                >= 0);
+    }
+
+    unsafe fn call_emit(client: *mut sd_bus) {
+        let mut call = ptr::null_mut();
+        assert_eq!(sd_bus_message_new_method_call(client,
+                                                  &mut call,
+                                                  ptr::null(),
+                                                  c!("/org/example/test"),
+                                                  c!("org.example.Test"),
+                                                  c!("Emit")),
+                   0);
+        let mut reply = ptr::null_mut();
+        assert!(sd_bus_call(client, call, 0, ptr::null_mut(), &mut reply) > 0);
+        sd_bus_message_unref(reply);
+        sd_bus_message_unref(call);
+    }
+
+    // stores the `u` argument of a reply or signal in the `Option<u32>` passed
+    // as userdata
+    unsafe extern "C" fn receive(message: *mut sd_bus_message,
+                                 userdata: *mut c_void,
+                                 _error: *mut sd_bus_error)
+                                 -> c_int {
+        let mut value = 0u32;
+        let result = sd_bus_message_read_basic(message,
+                                               SD_BUS_TYPE_UINT32,
+                                               &mut value as *mut u32 as *mut c_void);
+        if result < 0 {
+            return result;
+        }
+        *(userdata as *mut Option<u32>) = Some(value);
+        1
+    }
+
+    // processes the messages of `bus` until `received` holds a value
+    unsafe fn process_until_received(bus: *mut sd_bus, received: *const Option<u32>) -> u32 {
+        loop {
+            if let Some(value) = *received {
+                return value;
+            }
+            let result = sd_bus_process(bus, ptr::null_mut());
+            assert!(result >= 0);
+            if result == 0 {
+                assert!(sd_bus_wait(bus, 5_000_000) > 0);
+            }
+        }
+    }
+
+    // increments the counter when dropped
+    struct Tracked(Arc<AtomicUsize>);
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    unsafe extern "C" fn destroy(userdata: *mut c_void) {
+        drop(Box::from_raw(userdata as *mut Tracked));
+    }
+
+    #[test]
+    fn call_async() {
+        unsafe {
+            let (client, thread) = connect(|server| export_double(server));
+            let mut call = ptr::null_mut();
+            assert_eq!(sd_bus_message_new_method_call(client,
+                                                      &mut call,
+                                                      ptr::null(),
+                                                      c!("/org/example/test"),
+                                                      c!("org.example.Test"),
+                                                      c!("Double")),
+                       0);
+            let value = 21u32;
+            assert!(sd_bus_message_append_basic(call,
+                                                SD_BUS_TYPE_UINT32,
+                                                &value as *const u32 as *const c_void)
+                    >= 0);
+            let received = Box::into_raw(Box::new(None::<u32>));
+            let mut slot = ptr::null_mut();
+            assert!(sd_bus_call_async(client,
+                                      &mut slot,
+                                      call,
+                                      Some(receive),
+                                      received as *mut c_void,
+                                      0)
+                    >= 0);
+            sd_bus_message_unref(call);
+            assert_eq!(sd_bus_slot_get_userdata(slot), received as *mut c_void);
+            assert_eq!(process_until_received(client, received), 42);
+            sd_bus_slot_unref(slot);
+            drop(Box::from_raw(received));
+            disconnect(client, thread);
+        }
+    }
+
+    #[cfg(not(feature = "dlopen"))]
+    #[test]
+    fn call_method_async() {
+        unsafe {
+            let (client, thread) = connect(|server| export_double(server));
+            let received = Box::into_raw(Box::new(None::<u32>));
+            assert!(sd_bus_call_method_async(client,
+                                             ptr::null_mut(),
+                                             ptr::null(),
+                                             c!("/org/example/test"),
+                                             c!("org.example.Test"),
+                                             c!("Double"),
+                                             Some(receive),
+                                             received as *mut c_void,
+                                             c!("u"),
+                                             8u32)
+                    >= 0);
+            assert_eq!(process_until_received(client, received), 16);
+            drop(Box::from_raw(received));
+            disconnect(client, thread);
+        }
+    }
+
+    #[test]
+    fn match_signal() {
+        unsafe {
+            let (client, thread) = connect(|server| export_emit(server));
+            let received = Box::into_raw(Box::new(None::<u32>));
+            let mut slot = ptr::null_mut();
+            assert!(sd_bus_match_signal(client,
+                                        &mut slot,
+                                        ptr::null(),
+                                        c!("/org/example/test"),
+                                        c!("org.example.Test"),
+                                        c!("Doubled"),
+                                        Some(receive),
+                                        received as *mut c_void)
+                    >= 0);
+            call_emit(client);
+            assert_eq!(process_until_received(client, received), 7);
+            sd_bus_slot_unref(slot);
+            drop(Box::from_raw(received));
+            disconnect(client, thread);
+        }
+    }
+
+    #[test]
+    fn slot_destroy_callback() {
+        unsafe {
+            let (client, thread) = connect(|_| ());
+            let dropped = Arc::new(AtomicUsize::new(0));
+            let tracked = Box::into_raw(Box::new(Tracked(dropped.clone())));
+            let mut slot = ptr::null_mut();
+            assert!(sd_bus_match_signal(client,
+                                        &mut slot,
+                                        ptr::null(),
+                                        ptr::null(),
+                                        c!("org.example.Test"),
+                                        ptr::null(),
+                                        Some(receive),
+                                        tracked as *mut c_void)
+                    >= 0);
+            assert_eq!(sd_bus_slot_set_destroy_callback(slot, Some(destroy)), 0);
+            let mut callback = None;
+            assert_eq!(sd_bus_slot_get_destroy_callback(slot, &mut callback), 1);
+            assert!(callback.is_some());
+            assert_eq!(dropped.load(Ordering::SeqCst), 0);
+            assert!(sd_bus_slot_unref(slot).is_null());
+            assert_eq!(dropped.load(Ordering::SeqCst), 1);
+            assert_eq!(Arc::strong_count(&dropped), 1);
+            disconnect(client, thread);
+            assert_eq!(dropped.load(Ordering::SeqCst), 1);
+        }
+    }
+
+    #[test]
+    fn floating_slot() {
+        unsafe {
+            let (client, thread) = connect(|_| ());
+            let dropped = Arc::new(AtomicUsize::new(0));
+            let tracked = Box::into_raw(Box::new(Tracked(dropped.clone())));
+            let mut slot = ptr::null_mut();
+            assert!(sd_bus_match_signal(client,
+                                        &mut slot,
+                                        ptr::null(),
+                                        ptr::null(),
+                                        c!("org.example.Test"),
+                                        ptr::null(),
+                                        Some(receive),
+                                        tracked as *mut c_void)
+                    >= 0);
+            assert_eq!(sd_bus_slot_set_destroy_callback(slot, Some(destroy)), 0);
+            assert_eq!(sd_bus_slot_get_floating(slot), 0);
+            assert!(sd_bus_slot_set_floating(slot, 1) >= 0);
+            assert_eq!(sd_bus_slot_get_floating(slot), 1);
+            // the bus holds the only reference now
+            assert!(sd_bus_slot_unref(slot).is_null());
+            assert_eq!(dropped.load(Ordering::SeqCst), 0);
+            disconnect(client, thread);
+            assert_eq!(dropped.load(Ordering::SeqCst), 1);
+            assert_eq!(Arc::strong_count(&dropped), 1);
+        }
+    }
+}
@@ -0,0 +1,264 @@
+// sd-sys: FFI bindings to systemd for sd-id128 & sd-journal
+// Copyright (C) 2020 Christian Klaue [mail@ck76.de]
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use libc::{c_char, c_int, c_uint, pid_t, size_t, sockaddr};
+
+/// Pure Rust implementation of the `$NOTIFY_SOCKET` protocol for systems
+/// without libsystemd
+///
+/// The functions share the signatures and return values of their FFI
+/// counterparts. The variadic `sd_notifyf` functions are not available, format
+/// the state in Rust instead.
+///
+/// <https://www.freedesktop.org/software/systemd/man/sd_notify.html#>
+pub mod native;
+
+// prefixes for log lines written to stderr, interpreted by the journal as
+// syslog priority
+//
+// <https://www.freedesktop.org/software/systemd/man/sd-daemon.html#>
+pub const SD_EMERG: &str = "<0>";
+pub const SD_ALERT: &str = "<1>";
+pub const SD_CRIT: &str = "<2>";
+pub const SD_ERR: &str = "<3>";
+pub const SD_WARNING: &str = "<4>";
+pub const SD_NOTICE: &str = "<5>";
+pub const SD_INFO: &str = "<6>";
+pub const SD_DEBUG: &str = "<7>";
+
+/// `#define SD_LISTEN_FDS_START 3`
+///
+/// First file descriptor passed by socket activation.
+pub const SD_LISTEN_FDS_START: c_int = 3;
+
+// variadic functions, not available with feature `dlopen` (see `sd_extern!`)
+#[cfg(not(feature = "dlopen"))]
+extern "C" {
+    /// `int sd_notifyf(int unset_environment, const char *format, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_notify.html#>
+    pub fn sd_notifyf(unset_environment: c_int, format: *const c_char, ...) -> c_int;
+    /// `int sd_pid_notifyf_with_fds(pid_t pid, int unset_environment,
+    ///             const int *fds, size_t n_fds, const char *format, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_notify.html#>
+    #[cfg(systemd_v254)]
+    pub fn sd_pid_notifyf_with_fds(pid: pid_t,
+                                   unset_environment: c_int,
+                                   fds: *const c_int,
+                                   count: size_t,
+                                   format: *const c_char,
+                                   ...)
+                                   -> c_int;
+}
+
+sd_extern! {
+    /// `int sd_notify(int unset_environment, const char *state);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_notify.html#>
+    pub fn sd_notify(unset_environment: c_int, state: *const c_char) -> c_int;
+    /// `int sd_pid_notify(pid_t pid, int unset_environment, const char *state);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_notify.html#>
+    pub fn sd_pid_notify(pid: pid_t, unset_environment: c_int, state: *const c_char) -> c_int;
+    /// `int sd_pid_notify_with_fds(pid_t pid, int unset_environment,
+    ///             const char *state, const int *fds, unsigned n_fds);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_notify.html#>
+    pub fn sd_pid_notify_with_fds(pid: pid_t,
+                                  unset_environment: c_int,
+                                  state: *const c_char,
+                                  fds: *const c_int,
+                                  count: c_uint)
+                                  -> c_int;
+    /// `int sd_notify_barrier(int unset_environment, uint64_t timeout);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_notify.html#>
+    #[cfg(systemd_v246)]
+    pub fn sd_notify_barrier(unset_environment: c_int, timeout: u64) -> c_int;
+    /// `int sd_listen_fds(int unset_environment);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_listen_fds.html#>
+    pub fn sd_listen_fds(unset_environment: c_int) -> c_int;
+    /// `int sd_listen_fds_with_names(int unset_environment, char ***names);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_listen_fds.html#>
+    pub fn sd_listen_fds_with_names(unset_environment: c_int, names: *mut *mut *mut c_char) -> c_int;
+    /// `int sd_is_fifo(int fd, const char *path);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_is_fifo.html#>
+    pub fn sd_is_fifo(fd: c_int, path: *const c_char) -> c_int;
+    /// `int sd_is_special(int fd, const char *path);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_is_fifo.html#>
+    pub fn sd_is_special(fd: c_int, path: *const c_char) -> c_int;
+    /// `int sd_is_socket(int fd, int family, int type, int listening);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_is_fifo.html#>
+    pub fn sd_is_socket(fd: c_int, family: c_int, kind: c_int, listening: c_int) -> c_int;
+    /// `int sd_is_socket_inet(int fd, int family, int type, int listening,
+    ///                                 uint16_t port);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_is_fifo.html#>
+    pub fn sd_is_socket_inet(fd: c_int,
+                             family: c_int,
+                             kind: c_int,
+                             listening: c_int,
+                             port: u16)
+                             -> c_int;
+    /// `int sd_is_socket_sockaddr(int fd, int type, const struct sockaddr *addr,
+    ///                                 unsigned addr_len, int listening);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_is_fifo.html#>
+    pub fn sd_is_socket_sockaddr(fd: c_int,
+                                 kind: c_int,
+                                 address: *const sockaddr,
+                                 length: c_uint,
+                                 listening: c_int)
+                                 -> c_int;
+    /// `int sd_is_socket_unix(int fd, int type, int listening, const char *path,
+    ///                                 size_t length);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_is_fifo.html#>
+    pub fn sd_is_socket_unix(fd: c_int,
+                             kind: c_int,
+                             listening: c_int,
+                             path: *const c_char,
+                             length: size_t)
+                             -> c_int;
+    /// `int sd_is_mq(int fd, const char *path);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_is_fifo.html#>
+    pub fn sd_is_mq(fd: c_int, path: *const c_char) -> c_int;
+    /// `int sd_booted(void);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_booted.html#>
+    pub fn sd_booted() -> c_int;
+    /// `int sd_watchdog_enabled(int unset_environment, uint64_t *usec);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_watchdog_enabled.html#>
+    pub fn sd_watchdog_enabled(unset_environment: c_int, usec: *mut u64) -> c_int;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{native::tests::{lock, Receiver},
+                *};
+    use crate::login::free_strv;
+    use std::{env,
+              ffi::CStr,
+              fs::File,
+              os::unix::io::{AsRawFd, FromRawFd, OwnedFd},
+              ptr};
+
+    #[test]
+    fn notify_ready() {
+        let _lock = lock();
+        let receiver = Receiver::bind("ffi-ready");
+        assert_eq!(unsafe { sd_notify(1, b"READY=1\0".as_ptr() as *const c_char) },
+                   1);
+        let (payload, fds) = receiver.receive();
+        assert_eq!(payload, b"READY=1");
+        assert!(fds.is_empty());
+        assert!(env::var_os("NOTIFY_SOCKET").is_none());
+    }
+
+    #[test]
+    fn notify_like_native() {
+        let _lock = lock();
+        let receiver = Receiver::bind_abstract("ffi-native");
+        let state = b"STATUS=starting\nMAINPID=1\0".as_ptr() as *const c_char;
+        assert_eq!(unsafe { sd_pid_notify(0, 0, state) }, 1);
+        assert_eq!(unsafe { native::sd_pid_notify(0, 0, state) }, 1);
+        assert_eq!(receiver.receive().0, receiver.receive().0);
+    }
+
+    #[test]
+    fn listen_fds_with_names() {
+        let _lock = lock();
+        // sd_listen_fds sets FD_CLOEXEC on the passed descriptors and fails if
+        // they are not open
+        let mut opened = Vec::new();
+        while unsafe {
+            libc::fcntl(SD_LISTEN_FDS_START, libc::F_GETFD) < 0
+            || libc::fcntl(SD_LISTEN_FDS_START + 1, libc::F_GETFD) < 0
+        } {
+            opened.push(File::open("/dev/null").unwrap());
+        }
+        env::set_var("LISTEN_PID", std::process::id().to_string());
+        env::set_var("LISTEN_FDS", "2");
+        env::set_var("LISTEN_FDNAMES", "http:admin");
+        let mut names = ptr::null_mut();
+        assert_eq!(unsafe { sd_listen_fds_with_names(1, &mut names) }, 2);
+        let received = unsafe {
+            let received = [CStr::from_ptr(*names), CStr::from_ptr(*names.add(1))];
+            assert!((*names.add(2)).is_null());
+            received.map(|name| name.to_str().unwrap().to_owned())
+        };
+        unsafe { free_strv(names) };
+        assert_eq!(received, ["http", "admin"]);
+        for variable in &["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+            assert!(env::var_os(variable).is_none(), "{}", variable);
+        }
+        assert_eq!(unsafe { sd_listen_fds(0) }, 0);
+        // descriptors passed to another process
+        env::set_var("LISTEN_PID", "1");
+        env::set_var("LISTEN_FDS", "2");
+        assert_eq!(unsafe { sd_listen_fds(1) }, 0);
+    }
+
+    #[test]
+    fn watchdog_enabled() {
+        let _lock = lock();
+        let mut usec = 0;
+        env::set_var("WATCHDOG_USEC", "5000000");
+        env::set_var("WATCHDOG_PID", std::process::id().to_string());
+        assert!(unsafe { sd_watchdog_enabled(0, &mut usec) } > 0);
+        assert_eq!(usec, 5_000_000);
+        env::set_var("WATCHDOG_PID", "1");
+        assert_eq!(unsafe { sd_watchdog_enabled(0, &mut usec) }, 0);
+        env::remove_var("WATCHDOG_PID");
+        assert!(unsafe { sd_watchdog_enabled(1, &mut usec) } > 0);
+        assert!(env::var_os("WATCHDOG_USEC").is_none());
+        assert_eq!(unsafe { sd_watchdog_enabled(0, &mut usec) }, 0);
+    }
+
+    #[test]
+    fn is_socket_unix() {
+        let mut pair = [-1; 2];
+        assert_eq!(unsafe {
+                       libc::socketpair(libc::AF_UNIX,
+                                        libc::SOCK_STREAM | libc::SOCK_CLOEXEC,
+                                        0,
+                                        pair.as_mut_ptr())
+                   },
+                   0);
+        let pair = unsafe { [OwnedFd::from_raw_fd(pair[0]), OwnedFd::from_raw_fd(pair[1])] };
+        let fd = pair[0].as_raw_fd();
+        unsafe {
+            assert_eq!(sd_is_socket_unix(fd, libc::SOCK_STREAM, 0, ptr::null(), 0),
+                       1);
+            assert_eq!(sd_is_socket_unix(fd, libc::SOCK_STREAM, -1, ptr::null(), 0),
+                       1);
+            assert_eq!(sd_is_socket_unix(fd, libc::SOCK_DGRAM, -1, ptr::null(), 0),
+                       0);
+            assert_eq!(sd_is_socket_unix(fd, libc::SOCK_STREAM, 1, ptr::null(), 0),
+                       0);
+            assert_eq!(sd_is_socket(fd, libc::AF_UNIX, 0, -1), 1);
+            assert_eq!(sd_is_socket(fd, libc::AF_INET, 0, -1), 0);
+            assert_eq!(sd_is_fifo(fd, ptr::null()), 0);
+        }
+    }
+}
@@ -0,0 +1,413 @@
+// sd-sys: FFI bindings to systemd for sd-id128 & sd-journal
+// Copyright (C) 2020 Christian Klaue [mail@ck76.de]
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use libc::{c_char, c_int, c_uint, c_void, iovec, msghdr, pid_t, pollfd, sockaddr_un, socklen_t,
+           ucred, AF_UNIX, EINVAL, ETIMEDOUT, MSG_NOSIGNAL, O_CLOEXEC, SCM_CREDENTIALS,
+           SCM_RIGHTS, SOCK_CLOEXEC, SOCK_DGRAM, SOL_SOCKET};
+use std::{env, ffi::CStr, io, mem, os::unix::ffi::OsStrExt, ptr};
+
+const NOTIFY_SOCKET: &str = "NOTIFY_SOCKET";
+
+/// Pure Rust implementation of `sd_notify`.
+///
+/// # Safety
+///
+/// `state` must point to a NUL terminated string.
+pub unsafe fn sd_notify(unset_environment: c_int, state: *const c_char) -> c_int {
+    sd_pid_notify_with_fds(0, unset_environment, state, ptr::null(), 0)
+}
+
+/// Pure Rust implementation of `sd_pid_notify`.
+///
+/// # Safety
+///
+/// `state` must point to a NUL terminated string.
+pub unsafe fn sd_pid_notify(pid: pid_t, unset_environment: c_int, state: *const c_char) -> c_int {
+    sd_pid_notify_with_fds(pid, unset_environment, state, ptr::null(), 0)
+}
+
+/// Pure Rust implementation of `sd_pid_notify_with_fds`.
+///
+/// # Safety
+///
+/// `state` must point to a NUL terminated string, `fds` to `count` file
+/// descriptors.
+pub unsafe fn sd_pid_notify_with_fds(pid: pid_t,
+                                     unset_environment: c_int,
+                                     state: *const c_char,
+                                     fds: *const c_int,
+                                     count: c_uint)
+                                     -> c_int {
+    let result = notify(pid, state, fds, count);
+    if unset_environment != 0 {
+        env::remove_var(NOTIFY_SOCKET);
+    }
+    result
+}
+
+/// Pure Rust implementation of `sd_notify_barrier`.
+///
+/// `timeout` is given in µs, `u64::MAX` waits forever.
+///
+/// # Safety
+///
+/// Safe to call, `unsafe` for symmetry with the FFI binding only.
+pub unsafe fn sd_notify_barrier(unset_environment: c_int, timeout: u64) -> c_int {
+    let mut pipe = [-1; 2];
+    if libc::pipe2(pipe.as_mut_ptr(), O_CLOEXEC) < 0 {
+        return -errno();
+    }
+    let result = sd_pid_notify_with_fds(0,
+                                        unset_environment,
+                                        b"BARRIER=1\0".as_ptr() as *const c_char,
+                                        &pipe[1],
+                                        1);
+    libc::close(pipe[1]);
+    if result <= 0 {
+        libc::close(pipe[0]);
+        return result;
+    }
+    // the service manager closes its copy of the write end once it processed
+    // all previous notifications
+    let timeout = if timeout == u64::MAX {
+        -1
+    } else {
+        timeout.div_ceil(1000).min(c_int::MAX as u64) as c_int
+    };
+    let mut poll = pollfd { fd:      pipe[0],
+                            events:  0,
+                            revents: 0 };
+    let result = loop {
+        match libc::poll(&mut poll, 1, timeout) {
+            r if r < 0 && errno() == libc::EINTR => continue,
+            r if r < 0 => break -errno(),
+            0 => break -ETIMEDOUT,
+            _ => break 1
+        }
+    };
+    libc::close(pipe[0]);
+    result
+}
+
+fn errno() -> c_int {
+    io::Error::last_os_error().raw_os_error().unwrap_or(EINVAL)
+}
+
+// Address of $NOTIFY_SOCKET: an absolute path or an abstract socket if it
+// starts with '@'. Like libsystemd, a bare "/" or "@" is rejected.
+fn address(socket: &[u8]) -> Option<(sockaddr_un, socklen_t)> {
+    if socket.len() < 2 {
+        return None;
+    }
+    let mut address: sockaddr_un = unsafe { mem::zeroed() };
+    address.sun_family = AF_UNIX as libc::sa_family_t;
+    let offset = mem::size_of_val(&address.sun_family);
+    let length = match socket.first() {
+        // abstract socket, the name is not NUL terminated
+        Some(b'@') if socket.len() <= address.sun_path.len() => socket.len(),
+        // path, leave room for the NUL terminator
+        Some(b'/') if socket.len() < address.sun_path.len() => socket.len() + 1,
+        _ => return None
+    };
+    for (target, source) in address.sun_path.iter_mut().zip(socket.iter()) {
+        *target = *source as c_char;
+    }
+    if socket[0] == b'@' {
+        address.sun_path[0] = 0;
+    }
+    Some((address, (offset + length) as socklen_t))
+}
+
+unsafe fn notify(pid: pid_t, state: *const c_char, fds: *const c_int, count: c_uint) -> c_int {
+    if state.is_null() || (count > 0 && fds.is_null()) {
+        return -EINVAL;
+    }
+    let socket = match env::var_os(NOTIFY_SOCKET) {
+        Some(socket) => socket,
+        None => return 0
+    };
+    let (mut address, address_length) = match address(socket.as_bytes()) {
+        Some(address) => address,
+        None => return -EINVAL
+    };
+    let fd = libc::socket(AF_UNIX, SOCK_DGRAM | SOCK_CLOEXEC, 0);
+    if fd < 0 {
+        return -errno();
+    }
+
+    let state = CStr::from_ptr(state).to_bytes();
+    let mut iov = iovec { iov_base: state.as_ptr() as *mut c_void,
+                          iov_len:  state.len() };
+    let mut message: msghdr = mem::zeroed();
+    message.msg_name = &mut address as *mut sockaddr_un as *mut c_void;
+    message.msg_namelen = address_length;
+    message.msg_iov = &mut iov;
+    message.msg_iovlen = 1;
+
+    // credentials are only needed if they differ from those the kernel attaches
+    // anyway
+    let send_credentials = (pid != 0 && pid != libc::getpid())
+                           || libc::getuid() != libc::geteuid()
+                           || libc::getgid() != libc::getegid();
+    let rights_length = mem::size_of::<c_int>() as c_uint * count;
+    let rights_space = if count > 0 {
+        libc::CMSG_SPACE(rights_length) as usize
+    } else {
+        0
+    };
+    let credentials_space = if send_credentials {
+        libc::CMSG_SPACE(mem::size_of::<ucred>() as c_uint) as usize
+    } else {
+        0
+    };
+    // u64 for the alignment of cmsghdr
+    let mut control = vec![0u64; (rights_space + credentials_space).div_ceil(8)];
+    if rights_space + credentials_space > 0 {
+        message.msg_control = control.as_mut_ptr() as *mut c_void;
+        message.msg_controllen = (rights_space + credentials_space) as _;
+        let mut header = libc::CMSG_FIRSTHDR(&message);
+        if count > 0 {
+            (*header).cmsg_level = SOL_SOCKET;
+            (*header).cmsg_type = SCM_RIGHTS;
+            (*header).cmsg_len = libc::CMSG_LEN(rights_length) as _;
+            ptr::copy_nonoverlapping(fds, libc::CMSG_DATA(header) as *mut c_int, count as usize);
+            header = libc::CMSG_NXTHDR(&message, header);
+        }
+        if send_credentials {
+            (*header).cmsg_level = SOL_SOCKET;
+            (*header).cmsg_type = SCM_CREDENTIALS;
+            (*header).cmsg_len = libc::CMSG_LEN(mem::size_of::<ucred>() as c_uint) as _;
+            let credentials = ucred { pid: if pid != 0 { pid } else { libc::getpid() },
+                                      uid: libc::getuid(),
+                                      gid: libc::getgid() };
+            ptr::write_unaligned(libc::CMSG_DATA(header) as *mut ucred, credentials);
+        }
+    }
+
+    let mut result = libc::sendmsg(fd, &message, MSG_NOSIGNAL);
+    // sending credentials of another process requires privileges, retry with
+    // those attached by the kernel
+    if result < 0 && send_credentials {
+        message.msg_controllen = rights_space as _;
+        if rights_space == 0 {
+            message.msg_control = ptr::null_mut();
+        }
+        result = libc::sendmsg(fd, &message, MSG_NOSIGNAL);
+    }
+    let result = if result < 0 { -errno() } else { 1 };
+    libc::close(fd);
+    result
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use std::{ffi::CString,
+              os::unix::{io::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+                         net::UnixDatagram},
+              path::PathBuf,
+              sync::{Mutex, MutexGuard},
+              thread,
+              time::Duration};
+
+    // the tests change $NOTIFY_SOCKET of the whole process
+    static ENVIRONMENT: Mutex<()> = Mutex::new(());
+
+    pub(crate) fn lock() -> MutexGuard<'static, ()> {
+        ENVIRONMENT.lock()
+                   .unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Socket of a fake service manager, `$NOTIFY_SOCKET` points to it.
+    pub(crate) struct Receiver {
+        socket: UnixDatagram,
+        path:   Option<PathBuf>
+    }
+
+    impl Receiver {
+        pub(crate) fn bind(name: &str) -> Receiver {
+            let path = env::temp_dir().join(format!("sd-sys-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            let socket = UnixDatagram::bind(&path).unwrap();
+            env::set_var(NOTIFY_SOCKET, &path);
+            Receiver::new(socket, Some(path))
+        }
+
+        pub(crate) fn bind_abstract(name: &str) -> Receiver {
+            use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+            let name = format!("sd-sys-{}-{}", name, std::process::id());
+            let address = SocketAddr::from_abstract_name(name.as_bytes()).unwrap();
+            let socket = UnixDatagram::bind_addr(&address).unwrap();
+            env::set_var(NOTIFY_SOCKET, format!("@{}", name));
+            Receiver::new(socket, None)
+        }
+
+        fn new(socket: UnixDatagram, path: Option<PathBuf>) -> Receiver {
+            socket.set_read_timeout(Some(Duration::from_secs(5)))
+                  .unwrap();
+            Receiver { socket,
+                       path }
+        }
+
+        /// Receives one datagram and the file descriptors passed along.
+        pub(crate) fn receive(&self) -> (Vec<u8>, Vec<OwnedFd>) {
+            let mut buffer = [0u8; 4096];
+            let mut control = [0u64; 64];
+            let mut iov = iovec { iov_base: buffer.as_mut_ptr() as *mut c_void,
+                                  iov_len:  buffer.len() };
+            let mut fds = Vec::new();
+            unsafe {
+                let mut message: msghdr = mem::zeroed();
+                message.msg_iov = &mut iov;
+                message.msg_iovlen = 1;
+                message.msg_control = control.as_mut_ptr() as *mut c_void;
+                message.msg_controllen = mem::size_of_val(&control) as _;
+                let length = libc::recvmsg(self.socket.as_raw_fd(),
+                                           &mut message,
+                                           libc::MSG_CMSG_CLOEXEC);
+                assert!(length >= 0, "{}", io::Error::last_os_error());
+                let mut header = libc::CMSG_FIRSTHDR(&message);
+                while !header.is_null() {
+                    if (*header).cmsg_level == SOL_SOCKET && (*header).cmsg_type == SCM_RIGHTS {
+                        let count = ((*header).cmsg_len as usize - libc::CMSG_LEN(0) as usize)
+                                    / mem::size_of::<c_int>();
+                        let data = libc::CMSG_DATA(header) as *const c_int;
+                        for i in 0..count {
+                            let fd: RawFd = ptr::read_unaligned(data.add(i));
+                            fds.push(OwnedFd::from_raw_fd(fd));
+                        }
+                    }
+                    header = libc::CMSG_NXTHDR(&message, header);
+                }
+                (buffer[..length as usize].to_vec(), fds)
+            }
+        }
+    }
+
+    impl Drop for Receiver {
+        fn drop(&mut self) {
+            env::remove_var(NOTIFY_SOCKET);
+            if let Some(path) = &self.path {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    fn state(state: &str) -> CString {
+        CString::new(state).unwrap()
+    }
+
+    #[test]
+    fn notify_path() {
+        let _lock = lock();
+        let receiver = Receiver::bind("notify-path");
+        let state = state("READY=1\nSTATUS=running");
+        assert_eq!(unsafe { sd_notify(0, state.as_ptr()) }, 1);
+        let (payload, fds) = receiver.receive();
+        assert_eq!(payload, b"READY=1\nSTATUS=running");
+        assert!(fds.is_empty());
+        assert!(env::var_os(NOTIFY_SOCKET).is_some());
+    }
+
+    #[test]
+    fn notify_abstract() {
+        let _lock = lock();
+        let receiver = Receiver::bind_abstract("notify-abstract");
+        let state = state("RELOADING=1");
+        assert_eq!(unsafe { sd_pid_notify(0, 0, state.as_ptr()) }, 1);
+        assert_eq!(receiver.receive().0, b"RELOADING=1");
+    }
+
+    #[test]
+    fn notify_with_fds() {
+        let _lock = lock();
+        let receiver = Receiver::bind("notify-fds");
+        let mut pipe = [-1; 2];
+        assert_eq!(unsafe { libc::pipe2(pipe.as_mut_ptr(), O_CLOEXEC) }, 0);
+        let (read, write) =
+            unsafe { (OwnedFd::from_raw_fd(pipe[0]), OwnedFd::from_raw_fd(pipe[1])) };
+        let state = state("FDSTORE=1\nFDNAME=pipe");
+        let fds = [read.as_raw_fd()];
+        assert_eq!(unsafe { sd_pid_notify_with_fds(0, 0, state.as_ptr(), fds.as_ptr(), 1) },
+                   1);
+        let (payload, fds) = receiver.receive();
+        assert_eq!(payload, b"FDSTORE=1\nFDNAME=pipe");
+        assert_eq!(fds.len(), 1);
+        // the received descriptor refers to the same pipe
+        let mut byte = 0u8;
+        unsafe {
+            assert_eq!(libc::write(write.as_raw_fd(), b"x".as_ptr() as *const c_void, 1),
+                       1);
+            assert_eq!(libc::read(fds[0].as_raw_fd(), &mut byte as *mut u8 as *mut c_void, 1),
+                       1);
+        }
+        assert_eq!(byte, b'x');
+    }
+
+    #[test]
+    fn unset_environment() {
+        let _lock = lock();
+        let receiver = Receiver::bind("notify-unset");
+        let state = state("READY=1");
+        assert_eq!(unsafe { sd_notify(1, state.as_ptr()) }, 1);
+        assert_eq!(receiver.receive().0, b"READY=1");
+        assert!(env::var_os(NOTIFY_SOCKET).is_none());
+        assert_eq!(unsafe { sd_notify(0, state.as_ptr()) }, 0);
+    }
+
+    #[test]
+    fn invalid_socket() {
+        let _lock = lock();
+        let state = state("READY=1");
+        for socket in &["relative/path", "/", "@"] {
+            env::set_var(NOTIFY_SOCKET, socket);
+            assert_eq!(unsafe { sd_notify(0, state.as_ptr()) },
+                       -EINVAL,
+                       "{}",
+                       socket);
+            assert_eq!(unsafe { crate::daemon::sd_notify(0, state.as_ptr()) },
+                       -EINVAL,
+                       "{}",
+                       socket);
+        }
+        env::remove_var(NOTIFY_SOCKET);
+        assert_eq!(unsafe { sd_notify(0, state.as_ptr()) }, 0);
+    }
+
+    #[test]
+    fn barrier() {
+        let _lock = lock();
+        let receiver = Receiver::bind("notify-barrier");
+        // the service manager closes the passed descriptor after processing
+        let manager = thread::spawn(move || {
+            let (payload, fds) = receiver.receive();
+            assert_eq!(fds.len(), 1);
+            drop(fds);
+            (payload, receiver)
+        });
+        assert_eq!(unsafe { sd_notify_barrier(0, 5_000_000) }, 1);
+        let (payload, _receiver) = manager.join().unwrap();
+        assert_eq!(payload, b"BARRIER=1");
+    }
+
+    #[test]
+    fn barrier_timeout() {
+        let _lock = lock();
+        let receiver = Receiver::bind("notify-barrier-timeout");
+        // nobody receives the message, the descriptor stays open
+        assert_eq!(unsafe { sd_notify_barrier(0, 100_000) }, -ETIMEDOUT);
+        assert_eq!(receiver.receive().0, b"BARRIER=1");
+    }
+}
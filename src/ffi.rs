@@ -0,0 +1,179 @@
+// sd-sys: FFI bindings to systemd for sd-id128 & sd-journal
+// Copyright (C) 2020 Christian Klaue [mail@ck76.de]
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Declares functions exported by libsystemd.
+//
+// Without feature `dlopen` this expands to a plain `extern "C"` block linked
+// against libsystemd. With feature `dlopen` each function becomes an
+// `unsafe fn` with the same signature, which resolves its symbol from
+// libsystemd.so.0 on first use. If the library or the symbol is missing, the
+// function returns `-ENOSYS`, a null pointer or nothing depending on its
+// return type.
+//
+// Variadic functions cannot be declared with this macro: their `dlopen`
+// wrapper would itself have to be a variadic function, which cannot be defined
+// in stable Rust. They are declared in a separate `extern "C"` block gated by
+// `#[cfg(not(feature = "dlopen"))]` and are not available with feature
+// `dlopen`.
+macro_rules! sd_extern {
+    ($($(#[$attr:meta])* pub fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;)*) => {
+        #[cfg(not(feature = "dlopen"))]
+        extern "C" {
+            $($(#[$attr])* pub fn $name($($arg: $ty),*) $(-> $ret)?;)*
+        }
+        $(
+            #[cfg(feature = "dlopen")]
+            $(#[$attr])*
+            #[allow(clippy::missing_safety_doc, clippy::too_many_arguments)]
+            pub unsafe fn $name($($arg: $ty),*) $(-> $ret)? {
+                static SYMBOL: $crate::ffi::Symbol =
+                    $crate::ffi::Symbol::new(concat!(stringify!($name), "\0"));
+                match SYMBOL.address() {
+                    Some(address) => {
+                        let function: unsafe extern "C" fn($($ty),*) $(-> $ret)? =
+                            std::mem::transmute(address);
+                        function($($arg),*)
+                    },
+                    None => $crate::ffi::Missing::missing()
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "dlopen")]
+pub(crate) use self::dlopen::{Missing, Symbol};
+
+#[cfg(feature = "dlopen")]
+mod dlopen {
+    use libc::{c_char, c_int, c_void, ENOSYS, RTLD_LOCAL, RTLD_NOW};
+    use std::{ptr,
+              sync::{atomic::{AtomicBool, AtomicPtr, Ordering},
+                     Once}};
+
+    static LIBRARY_INIT: Once = Once::new();
+    static LIBRARY: AtomicPtr<c_void> = AtomicPtr::new(ptr::null_mut());
+
+    fn library() -> *mut c_void {
+        LIBRARY_INIT.call_once(|| {
+                        let name = b"libsystemd.so.0\0";
+                        let handle = unsafe {
+                            libc::dlopen(name.as_ptr() as *const c_char, RTLD_NOW | RTLD_LOCAL)
+                        };
+                        LIBRARY.store(handle, Ordering::Release);
+                    });
+        LIBRARY.load(Ordering::Acquire)
+    }
+
+    /// Lazily resolved symbol of libsystemd.
+    pub struct Symbol {
+        name:     &'static str,
+        address:  AtomicPtr<c_void>,
+        resolved: AtomicBool
+    }
+
+    impl Symbol {
+        /// `name` must be NUL terminated.
+        pub const fn new(name: &'static str) -> Symbol {
+            Symbol { name,
+                     address: AtomicPtr::new(ptr::null_mut()),
+                     resolved: AtomicBool::new(false) }
+        }
+
+        /// Address of the symbol or `None` if libsystemd or the symbol is not
+        /// available.
+        pub fn address(&self) -> Option<*mut c_void> {
+            // resolving twice from concurrent threads yields the same address
+            if !self.resolved.load(Ordering::Acquire) {
+                let library = library();
+                let address = if library.is_null() {
+                    ptr::null_mut()
+                } else {
+                    unsafe { libc::dlsym(library, self.name.as_ptr() as *const c_char) }
+                };
+                self.address.store(address, Ordering::Release);
+                self.resolved.store(true, Ordering::Release);
+            }
+            let address = self.address.load(Ordering::Acquire);
+            if address.is_null() {
+                None
+            } else {
+                Some(address)
+            }
+        }
+    }
+
+    /// Return value of a function whose symbol could not be resolved.
+    pub trait Missing {
+        fn missing() -> Self;
+    }
+
+    impl Missing for () {
+        fn missing() -> Self {}
+    }
+
+    impl Missing for c_int {
+        fn missing() -> Self {
+            -ENOSYS
+        }
+    }
+
+    impl<T> Missing for *mut T {
+        fn missing() -> Self {
+            ptr::null_mut()
+        }
+    }
+
+    impl<T> Missing for *const T {
+        fn missing() -> Self {
+            ptr::null()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "dlopen"))]
+mod tests {
+    use super::Symbol;
+    use libc::{c_int, c_void, ENOSYS};
+
+    sd_extern! {
+        pub fn sd_sys_missing(value: c_int) -> c_int;
+        pub fn sd_sys_missing_pointer() -> *mut c_void;
+        pub fn sd_sys_missing_void();
+    }
+
+    #[test]
+    fn resolve() {
+        static PRESENT: Symbol = Symbol::new("sd_id128_get_machine\0");
+        static MISSING: Symbol = Symbol::new("sd_sys_missing\0");
+        assert!(PRESENT.address().is_some());
+        assert!(MISSING.address().is_none());
+        // resolved once, the result is kept
+        assert!(PRESENT.address().is_some());
+        assert!(MISSING.address().is_none());
+    }
+
+    #[test]
+    fn missing() {
+        unsafe {
+            assert_eq!(sd_sys_missing(1), -ENOSYS);
+            assert!(sd_sys_missing_pointer().is_null());
+            sd_sys_missing_void();
+            let mut id = crate::id128::SD_ID128_NULL;
+            assert_ne!(crate::id128::sd_id128_get_machine(&mut id), -ENOSYS);
+        }
+    }
+}
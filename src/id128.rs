@@ -13,32 +13,357 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::hmac::hmac_sha256;
 use libc::{c_char, c_int};
+use std::{cmp::Ordering,
+          error::Error,
+          fmt,
+          hash::{Hash, Hasher},
+          str::{self, FromStr}};
+
+/// Size of the buffer for `sd_id128_to_string`, including the trailing NUL.
+pub const SD_ID128_STRING_MAX: usize = 33;
+/// Size of the buffer for `sd_id128_to_uuid_string`, including the trailing
+/// NUL.
+pub const SD_ID128_UUID_STRING_MAX: usize = 37;
+
+/// `SD_ID128_NULL`: the ID consisting of zeros only.
+pub const SD_ID128_NULL: sd_id128 = sd_id128::from_bytes([0x00; 16]);
+/// `SD_ID128_ALLF`: the ID consisting of 0xff bytes only.
+pub const SD_ID128_ALLF: sd_id128 = sd_id128::from_bytes([0xff; 16]);
+
 /// FFI data type mapping for sd-id128 as defined in libsystemd.
 ///
 /// This data type should rarely be used directly. Crate sd-id128 defines a
 /// wrapper `ID128`.
 ///
-/// While libsystemd defines the data type as a union of \[u8;16\] and \[u64;2\]
-/// currently sd-sys only supports the first.
+/// libsystemd defines the data type as a union of \[u8;16\] and \[u64;2\].
+/// Both fields cover the same 16 bytes, every bit pattern is valid for either
+/// of them. Use [`sd_id128::bytes`] and [`sd_id128::qwords`] to read them
+/// without `unsafe`.
 ///
 /// <https://www.freedesktop.org/software/systemd/man/sd-id128.html>
 #[allow(non_camel_case_types)]
 #[repr(C)]
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
-pub struct sd_id128 {
-    pub value: [u8; 16]
+#[derive(Clone, Copy)]
+pub union sd_id128 {
+    pub bytes:  [u8; 16],
+    pub qwords: [u64; 2]
+}
+
+impl sd_id128 {
+    /// Equivalent of `SD_ID128_MAKE`.
+    pub const fn from_bytes(bytes: [u8; 16]) -> sd_id128 {
+        sd_id128 { bytes }
+    }
+
+    /// Parses an ID from 32 hexadecimal characters or from the UUID format
+    /// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx` with the same rules as
+    /// `sd_id128_from_string`.
+    ///
+    /// Returns `None` if `string` is not a valid ID. See
+    /// [`sd_id128_make`](crate::sd_id128_make) to parse an ID at compile
+    /// time.
+    pub const fn parse(string: &str) -> Option<sd_id128> {
+        let string = string.as_bytes();
+        let mut bytes = [0u8; 16];
+        let mut n = 0;
+        let mut i = 0;
+        let mut uuid = false;
+        while n < 16 {
+            if i + 1 >= string.len() {
+                return None;
+            }
+            if string[i] == b'-' {
+                if i == 8 {
+                    uuid = true;
+                } else if !(uuid && (i == 13 || i == 18 || i == 23)) {
+                    return None;
+                }
+                i += 1;
+                continue;
+            }
+            let (high, low) = match (unhex(string[i]), unhex(string[i + 1])) {
+                (Some(high), Some(low)) => (high, low),
+                _ => return None
+            };
+            bytes[n] = high << 4 | low;
+            n += 1;
+            i += 2;
+        }
+        if i != string.len() || i != if uuid { 36 } else { 32 } {
+            return None;
+        }
+        Some(sd_id128::from_bytes(bytes))
+    }
+
+    /// Formats the ID as 32 lowercase hexadecimal characters followed by a
+    /// NUL, like `sd_id128_to_string` and `SD_ID128_TO_STRING`.
+    pub const fn to_hex(&self) -> [u8; SD_ID128_STRING_MAX] {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let bytes = self.bytes();
+        let mut string = [0u8; SD_ID128_STRING_MAX];
+        let mut n = 0;
+        while n < 16 {
+            string[2 * n] = DIGITS[(bytes[n] >> 4) as usize];
+            string[2 * n + 1] = DIGITS[(bytes[n] & 0xf) as usize];
+            n += 1;
+        }
+        string
+    }
+
+    /// Byte view of the ID, identical to its string representation.
+    pub const fn bytes(&self) -> [u8; 16] {
+        unsafe { self.bytes }
+    }
+
+    /// 64-bit view of the ID in native byte order.
+    pub const fn qwords(&self) -> [u64; 2] {
+        unsafe { self.qwords }
+    }
+
+    /// Sets the version and variant bits of the ID to those of a random UUID
+    /// (version 4, RFC 4122 variant), like `SD_ID128_MAKE_UUID_STR` and the IDs
+    /// returned by `sd_id128_randomize` and the `*_app_specific` functions.
+    pub const fn make_v4_uuid(&self) -> sd_id128 {
+        let mut bytes = self.bytes();
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        sd_id128::from_bytes(bytes)
+    }
+
+    /// Derives an app-specific ID in pure Rust, like
+    /// `sd_id128_get_app_specific`: HMAC-SHA256 keyed with this ID over `app`,
+    /// truncated to 128 bits and made a version 4 UUID.
+    ///
+    /// Other than `sd_id128_get_machine_app_specific` and
+    /// `sd_id128_get_boot_app_specific` this does not depend on the running
+    /// system, e.g. `machine_id.app_specific(&app)` yields the ID for a
+    /// machine ID read from a disk image. The derivation matches libsystemd
+    /// since systemd 240.
+    pub fn app_specific(&self, app: &sd_id128) -> sd_id128 {
+        let hmac = hmac_sha256(&self.bytes(), &app.bytes());
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&hmac[..16]);
+        sd_id128::from_bytes(bytes).make_v4_uuid()
+    }
+
+    /// Equivalent of `sd_id128_equal`.
+    pub const fn equal(&self, other: &sd_id128) -> bool {
+        let (a, b) = (self.qwords(), other.qwords());
+        a[0] == b[0] && a[1] == b[1]
+    }
+
+    /// Equivalent of `sd_id128_is_null`.
+    pub const fn is_null(&self) -> bool {
+        self.equal(&SD_ID128_NULL)
+    }
+
+    /// Equivalent of `sd_id128_is_allf`.
+    pub const fn is_allf(&self) -> bool {
+        self.equal(&SD_ID128_ALLF)
+    }
+}
+
+const fn unhex(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None
+    }
+}
+
+/// Creates an [`sd_id128`](crate::id128::sd_id128) at compile time, like
+/// `SD_ID128_MAKE` in C.
+///
+/// Accepts either 16 bytes or a string literal of 32 hexadecimal characters or
+/// in UUID format. An invalid string fails compilation.
+///
+/// ```
+/// use sd_sys::{id128::sd_id128, sd_id128_make};
+///
+/// const MESSAGE_ID: sd_id128 = sd_id128_make!("fc2e22bc6ee647b6b90729ab34a250b1");
+/// const SAME_ID: sd_id128 = sd_id128_make!("fc2e22bc-6ee6-47b6-b907-29ab34a250b1");
+/// const BYTES: sd_id128 = sd_id128_make!(0xfc, 0x2e, 0x22, 0xbc, 0x6e, 0xe6, 0x47, 0xb6, 0xb9,
+///                                        0x07, 0x29, 0xab, 0x34, 0xa2, 0x50, 0xb1);
+/// assert!(MESSAGE_ID == SAME_ID && SAME_ID == BYTES);
+/// ```
+#[macro_export]
+macro_rules! sd_id128_make {
+    ($string:literal) => {{
+        const ID: $crate::id128::sd_id128 = match $crate::id128::sd_id128::parse($string) {
+            Some(id) => id,
+            None => panic!(concat!("invalid sd_id128: ", $string))
+        };
+        ID
+    }};
+    ($($byte:expr),+ $(,)?) => {
+        $crate::id128::sd_id128::from_bytes([$($byte),+])
+    };
+}
+
+impl Default for sd_id128 {
+    fn default() -> Self {
+        SD_ID128_NULL
+    }
+}
+
+impl fmt::Debug for sd_id128 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("sd_id128")
+         .field("bytes", &self.bytes())
+         .finish()
+    }
+}
+
+/// Formats the ID as 32 lowercase hexadecimal characters like
+/// `sd_id128_to_string`.
+impl fmt::Display for sd_id128 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let string = self.to_hex();
+        // to_hex only produces ASCII
+        f.pad(str::from_utf8(&string[..SD_ID128_STRING_MAX - 1]).unwrap_or_default())
+    }
+}
+
+/// Formats the ID like [`Display`](fmt::Display), the alternate flag `{:#x}`
+/// adds the prefix `0x` like for integers.
+impl fmt::LowerHex for sd_id128 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let string = self.to_hex();
+        // to_hex only produces ASCII
+        f.pad_integral(true,
+                       "0x",
+                       str::from_utf8(&string[..SD_ID128_STRING_MAX - 1]).unwrap_or_default())
+    }
+}
+
+/// Parses 32 hexadecimal characters or the UUID format like
+/// `sd_id128_from_string`.
+impl FromStr for sd_id128 {
+    type Err = ParseId128Error;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        sd_id128::parse(string).ok_or(ParseId128Error)
+    }
+}
+
+/// Error returned when parsing an [`sd_id128`] from a string fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseId128Error;
+
+impl fmt::Display for ParseId128Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid sd_id128: expected 32 hexadecimal characters or a UUID")
+    }
+}
+
+impl Error for ParseId128Error {}
+
+/// Serializes the ID as 32 lowercase hexadecimal characters.
+#[cfg(feature = "serde")]
+impl serde::Serialize for sd_id128 {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let string = self.to_hex();
+        // to_hex only produces ASCII
+        serializer.serialize_str(str::from_utf8(&string[..SD_ID128_STRING_MAX - 1]).unwrap_or_default())
+    }
 }
 
-extern "C" {
-    /// `char *sd_id128_to_string(sd_id128_t id, char s[33]);`
+/// Deserializes the ID from 32 hexadecimal characters or the UUID format.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for sd_id128 {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = sd_id128;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("32 hexadecimal characters or a UUID")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, string: &str) -> Result<sd_id128, E> {
+                sd_id128::parse(string).ok_or_else(|| {
+                                           E::invalid_value(serde::de::Unexpected::Str(string),
+                                                            &self)
+                                       })
+            }
+        }
+
+        deserializer.deserialize_str(Visitor)
+    }
+}
+
+/// Converts the bytes unchanged. The version and variant bits are kept as they
+/// are, see [`sd_id128::make_v4_uuid`] to turn an arbitrary ID into a random
+/// UUID.
+#[cfg(feature = "uuid")]
+impl From<uuid::Uuid> for sd_id128 {
+    fn from(uuid: uuid::Uuid) -> Self {
+        sd_id128::from_bytes(*uuid.as_bytes())
+    }
+}
+
+/// Converts the bytes unchanged. IDs generated by libsystemd are version 4
+/// UUIDs of the RFC 4122 variant, IDs read from elsewhere (e.g. an
+/// /etc/machine-id written by other tools) need not be.
+#[cfg(feature = "uuid")]
+impl From<sd_id128> for uuid::Uuid {
+    fn from(id: sd_id128) -> Self {
+        uuid::Uuid::from_bytes(id.bytes())
+    }
+}
+
+impl PartialEq for sd_id128 {
+    fn eq(&self, other: &Self) -> bool {
+        self.equal(other)
+    }
+}
+
+impl Eq for sd_id128 {}
+
+impl Hash for sd_id128 {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bytes().hash(state);
+    }
+}
+
+// ordered by bytes to match the ordering of the string representation
+impl Ord for sd_id128 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.bytes().cmp(&other.bytes())
+    }
+}
+
+impl PartialOrd for sd_id128 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+sd_extern! {
+    /// `char *sd_id128_to_string(sd_id128_t id,
+    ///                                 char s[static SD_ID128_STRING_MAX]);`
     ///
     /// <https://www.freedesktop.org/software/systemd/man/sd_id128_to_string.html#>
-    pub fn sd_id128_to_string(id: sd_id128, string: *const c_char) -> *mut c_char;
+    pub fn sd_id128_to_string(id: sd_id128, string: *mut c_char) -> *mut c_char;
     /// `int sd_id128_from_string(const char *s, sd_id128_t *ret);`
     ///
     /// <https://www.freedesktop.org/software/systemd/man/sd_id128_to_string.html#>
     pub fn sd_id128_from_string(string: *const c_char, id: *mut sd_id128) -> c_int;
+    /// `char *sd_id128_to_uuid_string(sd_id128_t id,
+    ///                                 char s[static SD_ID128_UUID_STRING_MAX]);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_id128_to_string.html#>
+    #[cfg(systemd_v251)]
+    pub fn sd_id128_to_uuid_string(id: sd_id128, string: *mut c_char) -> *mut c_char;
+    /// `int sd_id128_string_equal(const char *s, sd_id128_t id);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd-id128.html#>
+    #[cfg(systemd_v252)]
+    pub fn sd_id128_string_equal(string: *const c_char, id: sd_id128) -> c_int;
     /// `int sd_id128_randomize(sd_id128_t *ret);`
     ///
     /// <https://www.freedesktop.org/software/systemd/man/sd_id128_randomize.html#>
@@ -60,9 +385,168 @@ extern "C" {
     /// sd_id128_t *ret);`
     ///
     /// <https://www.freedesktop.org/software/systemd/man/sd_id128_get_machine.html#>
+    #[cfg(systemd_v240)]
     pub fn sd_id128_get_boot_app_specific(app: sd_id128, boot: *mut sd_id128) -> c_int;
     /// `int sd_id128_get_invocation(sd_id128_t *ret);`
     ///
     /// <https://www.freedesktop.org/software/systemd/man/sd_id128_get_machine.html#>
     pub fn sd_id128_get_invocation(id: *mut sd_id128) -> c_int;
+    /// `int sd_id128_get_invocation_app_specific(sd_id128_t app_id,
+    ///                                 sd_id128_t *ret);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_id128_get_machine.html#>
+    #[cfg(systemd_v255)]
+    pub fn sd_id128_get_invocation_app_specific(app: sd_id128, invocation: *mut sd_id128) -> c_int;
+    /// `int sd_id128_get_app_specific(sd_id128_t base, sd_id128_t app_id,
+    ///                                 sd_id128_t *ret);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_id128_get_machine.html#>
+    #[cfg(systemd_v255)]
+    pub fn sd_id128_get_app_specific(base: sd_id128, app: sd_id128, id: *mut sd_id128) -> c_int;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::{CStr, CString};
+
+    const ID: sd_id128 = sd_id128::from_bytes([0xfc, 0x2e, 0x22, 0xbc, 0x6e, 0xe6, 0x47, 0xb6,
+                                               0xb9, 0x07, 0x29, 0xab, 0x34, 0xa2, 0x50, 0xb1]);
+
+    // result of the libsystemd parser for comparison
+    fn from_string(string: &str) -> Option<sd_id128> {
+        let string = CString::new(string).unwrap();
+        let mut id = SD_ID128_NULL;
+        match unsafe { sd_id128_from_string(string.as_ptr(), &mut id) } {
+            0 => Some(id),
+            _ => None
+        }
+    }
+
+    #[test]
+    fn parse_valid() {
+        for string in &["fc2e22bc6ee647b6b90729ab34a250b1",
+                        "FC2E22BC6EE647B6B90729AB34A250B1",
+                        "fc2e22bc-6ee6-47b6-b907-29ab34a250b1",
+                        "FC2E22BC-6EE6-47B6-B907-29AB34A250B1"]
+        {
+            assert_eq!(sd_id128::parse(string), Some(ID), "{}", string);
+            assert_eq!(from_string(string), Some(ID), "{}", string);
+        }
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for string in &["",
+                        "fc2e22bc6ee647b6b90729ab34a250b",
+                        "fc2e22bc6ee647b6b90729ab34a250b1f",
+                        "fc2e22bc-6ee647b6-b907-29ab34a250b1",
+                        "fc2e22bc6ee6-47b6-b907-29ab34a250b1",
+                        "fc2e22bc-6ee6-47b6-b907-29ab34a250b1-",
+                        "fc2e22bc6ee647b6b90729ab34a250b1-",
+                        "fc2e22bc6ee647b6b90729ab34a250bg"]
+        {
+            assert_eq!(sd_id128::parse(string), None, "{}", string);
+            assert_eq!(from_string(string), None, "{}", string);
+            assert_eq!(string.parse::<sd_id128>(), Err(ParseId128Error));
+        }
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let mut buffer = [0 as c_char; SD_ID128_STRING_MAX];
+        for _ in 0..100 {
+            let mut id = SD_ID128_NULL;
+            assert_eq!(unsafe { sd_id128_randomize(&mut id) }, 0);
+            let string = id.to_string();
+            let ffi = unsafe { CStr::from_ptr(sd_id128_to_string(id, buffer.as_mut_ptr())) };
+            assert_eq!(string, ffi.to_str().unwrap());
+            assert_eq!(string.parse::<sd_id128>(), Ok(id));
+        }
+        assert_eq!(ID.to_string(), "fc2e22bc6ee647b6b90729ab34a250b1");
+    }
+
+    #[test]
+    fn app_specific() {
+        let app = sd_id128_make!("a6b8e1b6e3f14f1f9c0b5b0a7a1e2d3c");
+        if std::path::Path::new("/etc/machine-id").exists() {
+            let mut machine = SD_ID128_NULL;
+            let mut expected = SD_ID128_NULL;
+            assert_eq!(unsafe { sd_id128_get_machine(&mut machine) }, 0);
+            assert_eq!(unsafe { sd_id128_get_machine_app_specific(app, &mut expected) },
+                       0);
+            assert_eq!(machine.app_specific(&app), expected);
+        }
+        #[cfg(systemd_v240)]
+        {
+            let mut boot = SD_ID128_NULL;
+            let mut expected = SD_ID128_NULL;
+            assert_eq!(unsafe { sd_id128_get_boot(&mut boot) }, 0);
+            assert_eq!(unsafe { sd_id128_get_boot_app_specific(app, &mut expected) },
+                       0);
+            assert_eq!(boot.app_specific(&app), expected);
+        }
+    }
+
+    #[test]
+    fn format_flags() {
+        assert_eq!(format!("{}", ID), "fc2e22bc6ee647b6b90729ab34a250b1");
+        assert_eq!(format!("{:#}", ID), "fc2e22bc6ee647b6b90729ab34a250b1");
+        assert_eq!(format!("{:x}", ID), "fc2e22bc6ee647b6b90729ab34a250b1");
+        assert_eq!(format!("{:#x}", ID), "0xfc2e22bc6ee647b6b90729ab34a250b1");
+        assert_eq!(format!("{:#036x}", ID),
+                   "0x00fc2e22bc6ee647b6b90729ab34a250b1");
+        assert_eq!(format!("{:>34}", ID), "  fc2e22bc6ee647b6b90729ab34a250b1");
+    }
+
+    #[test]
+    fn null_and_allf() {
+        assert!(SD_ID128_NULL.is_null());
+        assert!(!SD_ID128_NULL.is_allf());
+        assert!(SD_ID128_ALLF.is_allf());
+        assert!(!SD_ID128_ALLF.is_null());
+        assert!(!ID.is_null() && !ID.is_allf());
+        assert_eq!(sd_id128::parse("ffffffffffffffffffffffffffffffff"),
+                   Some(SD_ID128_ALLF));
+        assert_eq!(sd_id128::parse("00000000000000000000000000000000"),
+                   Some(SD_ID128_NULL));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        assert_eq!(serde_json::to_string(&ID).unwrap(),
+                   "\"fc2e22bc6ee647b6b90729ab34a250b1\"");
+        for string in &["\"fc2e22bc6ee647b6b90729ab34a250b1\"",
+                        "\"fc2e22bc-6ee6-47b6-b907-29ab34a250b1\""]
+        {
+            assert_eq!(serde_json::from_str::<sd_id128>(string).unwrap(), ID);
+        }
+        for string in &["\"\"",
+                        "\"fc2e22bc6ee647b6b90729ab34a250b\"",
+                        "\"fc2e22bc6ee647b6b90729ab34a250bx\""]
+        {
+            let error = serde_json::from_str::<sd_id128>(string).unwrap_err();
+            assert!(error.to_string()
+                         .starts_with(&format!("invalid value: string {}, expected 32 \
+                                                hexadecimal characters or a UUID",
+                                               string)),
+                    "{}",
+                    error);
+        }
+        assert!(serde_json::from_str::<sd_id128>("42").is_err());
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn uuid() {
+        let uuid = uuid::Uuid::from(ID);
+        assert_eq!(uuid.to_string(), "fc2e22bc-6ee6-47b6-b907-29ab34a250b1");
+        assert_eq!(uuid.get_version_num(), 4);
+        assert_eq!(uuid.get_variant(), uuid::Variant::RFC4122);
+        assert_eq!(sd_id128::from(uuid), ID);
+        let uuid = uuid::Uuid::parse_str("fc2e22bc-6ee6-47b6-b907-29ab34a250b1").unwrap();
+        assert_eq!(sd_id128::from(uuid), ID);
+        assert_eq!(uuid::Uuid::from(sd_id128::from(uuid)), uuid);
+    }
 }
@@ -14,7 +14,9 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 use super::id128::sd_id128;
-use libc::{c_char, c_int, c_void, iovec, size_t};
+use libc::{c_char, c_int, c_uint, c_void, iovec, size_t};
+#[cfg(feature = "va_list")]
+use std::ffi::VaList;
 
 /// FFI data type mapping for sd-journal as defined in libsystemd
 ///
@@ -32,8 +34,171 @@ pub const SD_JOURNAL_RUNTIME_ONLY: c_int = 1 << 1;
 pub const SD_JOURNAL_SYSTEM: c_int = 1 << 2;
 pub const SD_JOURNAL_CURRENT_USER: c_int = 1 << 3;
 pub const SD_JOURNAL_OS_ROOT: c_int = 1 << 4;
+#[cfg(systemd_v245)]
 pub const SD_JOURNAL_ALL_NAMESPACES: c_int = 1 << 5;
+#[cfg(systemd_v245)]
 pub const SD_JOURNAL_INCLUDE_DEFAULT_NAMESPACE: c_int = 1 << 6;
+#[cfg(systemd_v246)]
+pub const SD_JOURNAL_TAKE_DIRECTORY_FD: c_int = 1 << 7;
+#[cfg(systemd_v254)]
+pub const SD_JOURNAL_ASSUME_IMMUTABLE: c_int = 1 << 8;
+#[deprecated(note = "use SD_JOURNAL_SYSTEM")]
+pub const SD_JOURNAL_SYSTEM_ONLY: c_int = SD_JOURNAL_SYSTEM;
+
+// flags unavailable in the selected libsystemd version are left out of the
+// masks used by `OpenFlags`
+#[cfg(systemd_v245)]
+const NAMESPACES: c_int = SD_JOURNAL_ALL_NAMESPACES | SD_JOURNAL_INCLUDE_DEFAULT_NAMESPACE;
+#[cfg(not(systemd_v245))]
+const NAMESPACES: c_int = 0;
+#[cfg(systemd_v246)]
+const TAKE_DIRECTORY_FD: c_int = SD_JOURNAL_TAKE_DIRECTORY_FD;
+#[cfg(not(systemd_v246))]
+const TAKE_DIRECTORY_FD: c_int = 0;
+#[cfg(systemd_v254)]
+const ASSUME_IMMUTABLE: c_int = SD_JOURNAL_ASSUME_IMMUTABLE;
+#[cfg(not(systemd_v254))]
+const ASSUME_IMMUTABLE: c_int = 0;
+
+/// Typed wrapper for the `SD_JOURNAL_*` open flags.
+///
+/// Each `sd_journal_open*` function accepts a different subset of the flags
+/// and fails with `-EINVAL` on any other flag. [`OpenFlags::check`] applies
+/// the same rules as libsystemd before the call is made.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OpenFlags(c_int);
+
+impl OpenFlags {
+    #[cfg(systemd_v245)]
+    pub const ALL_NAMESPACES: OpenFlags = OpenFlags(SD_JOURNAL_ALL_NAMESPACES);
+    #[cfg(systemd_v254)]
+    pub const ASSUME_IMMUTABLE: OpenFlags = OpenFlags(SD_JOURNAL_ASSUME_IMMUTABLE);
+    pub const CURRENT_USER: OpenFlags = OpenFlags(SD_JOURNAL_CURRENT_USER);
+    #[cfg(systemd_v245)]
+    pub const INCLUDE_DEFAULT_NAMESPACE: OpenFlags =
+        OpenFlags(SD_JOURNAL_INCLUDE_DEFAULT_NAMESPACE);
+    pub const LOCAL_ONLY: OpenFlags = OpenFlags(SD_JOURNAL_LOCAL_ONLY);
+    pub const OS_ROOT: OpenFlags = OpenFlags(SD_JOURNAL_OS_ROOT);
+    pub const RUNTIME_ONLY: OpenFlags = OpenFlags(SD_JOURNAL_RUNTIME_ONLY);
+    pub const SYSTEM: OpenFlags = OpenFlags(SD_JOURNAL_SYSTEM);
+    #[cfg(systemd_v246)]
+    pub const TAKE_DIRECTORY_FD: OpenFlags = OpenFlags(SD_JOURNAL_TAKE_DIRECTORY_FD);
+
+    /// All flags known to this version of sd-sys.
+    pub const fn all() -> OpenFlags {
+        OpenFlags(SD_JOURNAL_LOCAL_ONLY
+                  | SD_JOURNAL_RUNTIME_ONLY
+                  | SD_JOURNAL_SYSTEM
+                  | SD_JOURNAL_CURRENT_USER
+                  | SD_JOURNAL_OS_ROOT
+                  | NAMESPACES
+                  | TAKE_DIRECTORY_FD
+                  | ASSUME_IMMUTABLE)
+    }
+
+    pub const fn empty() -> OpenFlags {
+        OpenFlags(0)
+    }
+
+    pub const fn bits(self) -> c_int {
+        self.0
+    }
+
+    /// Returns `None` if `bits` contains an unknown flag.
+    pub const fn from_bits(bits: c_int) -> Option<OpenFlags> {
+        if bits & !OpenFlags::all().0 == 0 {
+            Some(OpenFlags(bits))
+        } else {
+            None
+        }
+    }
+
+    pub const fn contains(self, other: OpenFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn union(self, other: OpenFlags) -> OpenFlags {
+        OpenFlags(self.0 | other.0)
+    }
+
+    /// Validates the flags against the set accepted by `function`.
+    ///
+    /// Returns the raw flags for the FFI call or the offending flags.
+    pub const fn check(self, function: OpenFunction) -> Result<c_int, OpenFlags> {
+        let invalid = self.0 & !function.allowed().0;
+        if invalid == 0 {
+            Ok(self.0)
+        } else {
+            Err(OpenFlags(invalid))
+        }
+    }
+}
+
+impl std::ops::BitOr for OpenFlags {
+    type Output = OpenFlags;
+
+    fn bitor(self, other: OpenFlags) -> OpenFlags {
+        self.union(other)
+    }
+}
+
+impl std::ops::BitOrAssign for OpenFlags {
+    fn bitor_assign(&mut self, other: OpenFlags) {
+        self.0 |= other.0;
+    }
+}
+
+impl std::ops::BitAnd for OpenFlags {
+    type Output = OpenFlags;
+
+    fn bitand(self, other: OpenFlags) -> OpenFlags {
+        OpenFlags(self.0 & other.0)
+    }
+}
+
+/// The `sd_journal_open*` function an [`OpenFlags`] value is checked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpenFunction {
+    /// `sd_journal_open` and `sd_journal_open_namespace`
+    Open,
+    /// `sd_journal_open_directory`
+    Directory,
+    /// `sd_journal_open_directory_fd`
+    DirectoryFd,
+    /// `sd_journal_open_files`
+    Files,
+    /// `sd_journal_open_files_fd`
+    FilesFd
+}
+
+impl OpenFunction {
+    /// Flags accepted by the function, mirroring the `OPEN_*_ALLOWED_FLAGS`
+    /// masks in libsystemd.
+    pub const fn allowed(self) -> OpenFlags {
+        match self {
+            OpenFunction::Open => OpenFlags(SD_JOURNAL_LOCAL_ONLY
+                                            | SD_JOURNAL_RUNTIME_ONLY
+                                            | SD_JOURNAL_SYSTEM
+                                            | SD_JOURNAL_CURRENT_USER
+                                            | NAMESPACES
+                                            | ASSUME_IMMUTABLE),
+            OpenFunction::Directory => OpenFlags(SD_JOURNAL_OS_ROOT
+                                                 | SD_JOURNAL_SYSTEM
+                                                 | SD_JOURNAL_CURRENT_USER
+                                                 | ASSUME_IMMUTABLE),
+            OpenFunction::DirectoryFd => OpenFlags(SD_JOURNAL_OS_ROOT
+                                                   | SD_JOURNAL_SYSTEM
+                                                   | SD_JOURNAL_CURRENT_USER
+                                                   | TAKE_DIRECTORY_FD
+                                                   | ASSUME_IMMUTABLE),
+            OpenFunction::Files | OpenFunction::FilesFd => OpenFlags(ASSUME_IMMUTABLE)
+        }
+    }
+}
 
 pub const LOG_EMERG: c_int = 0;
 pub const LOG_ALERT: c_int = 1;
@@ -48,34 +213,87 @@ pub const SD_JOURNAL_NOP: c_int = 0;
 pub const SD_JOURNAL_APPEND: c_int = 1;
 pub const SD_JOURNAL_INVALIDATE: c_int = 2;
 
+// variadic functions, not available with feature `dlopen` (see `sd_extern!`)
+#[cfg(not(feature = "dlopen"))]
 extern "C" {
     /// `int sd_journal_print(int priority, const char *format, …);`
     ///
     /// <https://www.freedesktop.org/software/systemd/man/sd_journal_print.html#>
     pub fn sd_journal_print(priority: c_int, message: *const c_char, ...) -> c_int;
+    /// `int sd_journal_send(const char *format, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_journal_print.html#>
+    pub fn sd_journal_send(format: *const c_char, ...) -> c_int;
+    /// `int sd_journal_print_with_location(int priority, const char *file,
+    ///          const char *line, const char *func, const char *format, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_journal_print.html#>
+    pub fn sd_journal_print_with_location(priority: c_int,
+                                          file: *const c_char,
+                                          line: *const c_char,
+                                          func: *const c_char,
+                                          message: *const c_char,
+                                          ...)
+                                          -> c_int;
+    /// `int sd_journal_send_with_location(const char *file, const char *line,
+    ///                     const char *func, const char *format, …);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_journal_print.html#>
+    pub fn sd_journal_send_with_location(file: *const c_char,
+                                         line: *const c_char,
+                                         func: *const c_char,
+                                         format: *const c_char,
+                                         ...)
+                                         -> c_int;
+}
+
+sd_extern! {
     /// `int sd_journal_sendv(const struct iovec *iov, int n);`
     ///
     /// <https://www.freedesktop.org/software/systemd/man/sd_journal_print.html#>
     pub fn sd_journal_sendv(entry: *const iovec, count: c_int) -> c_int;
-    // not implemented:
-    // int sd_journal_printv(int priority, const char *format, va_list ap);
-    // int sd_journal_send(const char *format, …);
-    // int sd_journal_perror(const char *message);
-    // int sd_journal_print_with_location(const char *file, const char *line,
-    //                 const char *func, int priority, const char *format, …);
-    // int sd_journal_printv_with_location(int priority, const char *file, const
-    //          char *line, const char *func, const char *format, va_list ap);
-    // int sd_journal_send_with_location(const char *file, const char *line, const
-    //                                  char *func, const char *format, …);
-    // int sd_journal_sendv_with_location(const char *file, const char *line,
-    //                     const char *func, const struct iovec *iov, int n);
-    // int sd_journal_perror_with_location(const char *file, const char *line, const
-    //                                  char *func, const char *message);
-
-    // <https://www.freedesktop.org/software/systemd/man/sd_journal_stream_fd.html#>
-    // not implemented:
-    // int sd_journal_stream_fd(const char *identifier, int priority, int
-    //                                  level_prefix);
+    /// `int sd_journal_perror(const char *message);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_journal_print.html#>
+    pub fn sd_journal_perror(message: *const c_char) -> c_int;
+    /// `int sd_journal_sendv_with_location(const char *file, const char *line,
+    ///                     const char *func, const struct iovec *iov, int n);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_journal_print.html#>
+    pub fn sd_journal_sendv_with_location(file: *const c_char,
+                                          line: *const c_char,
+                                          func: *const c_char,
+                                          entry: *const iovec,
+                                          count: c_int)
+                                          -> c_int;
+    /// `int sd_journal_perror_with_location(const char *file, const char *line,
+    ///                                 const char *func, const char *message);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_journal_print.html#>
+    pub fn sd_journal_perror_with_location(file: *const c_char,
+                                           line: *const c_char,
+                                           func: *const c_char,
+                                           message: *const c_char)
+                                           -> c_int;
+
+    /// `int sd_journal_stream_fd(const char *identifier, int priority,
+    ///                                 int level_prefix);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_journal_stream_fd.html#>
+    pub fn sd_journal_stream_fd(identifier: *const c_char,
+                                priority: c_int,
+                                level_prefix: c_int)
+                                -> c_int;
+    /// `int sd_journal_stream_fd_with_namespace(const char *name_space,
+    ///             const char *identifier, int priority, int level_prefix);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_journal_stream_fd.html#>
+    #[cfg(systemd_v256)]
+    pub fn sd_journal_stream_fd_with_namespace(namespace: *const c_char,
+                                               identifier: *const c_char,
+                                               priority: c_int,
+                                               level_prefix: c_int)
+                                               -> c_int;
 
     /// `int sd_journal_open(sd_journal **ret, int flags);`
     ///
@@ -85,6 +303,7 @@ extern "C" {
     ///                                 int flags);`
     ///
     /// <https://www.freedesktop.org/software/systemd/man/sd_journal_open.html#>
+    #[cfg(systemd_v245)]
     pub fn sd_journal_open_namespace(journal: *mut *mut sd_journal,
                                      namespace: *const c_char,
                                      flags: c_int)
@@ -109,10 +328,22 @@ extern "C" {
     ///
     /// <https://www.freedesktop.org/software/systemd/man/sd_journal_open.html#>
     pub fn sd_journal_close(journal: *mut sd_journal);
-    // not implemented:
-    // int sd_journal_open_directory_fd(sd_journal **ret, int fd, int flags);
-    // int sd_journal_open_files_fd(sd_journal **ret, int fds[], unsigned n_fds,
-    //                                  int flags);
+    /// `int sd_journal_open_directory_fd(sd_journal **ret, int fd, int flags);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_journal_open.html#>
+    pub fn sd_journal_open_directory_fd(journal: *mut *mut sd_journal,
+                                        fd: c_int,
+                                        flags: c_int)
+                                        -> c_int;
+    /// `int sd_journal_open_files_fd(sd_journal **ret, int fds[],
+    ///                                 unsigned n_fds, int flags);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_journal_open.html#>
+    pub fn sd_journal_open_files_fd(journal: *mut *mut sd_journal,
+                                    fds: *const c_int,
+                                    count: c_uint,
+                                    flags: c_int)
+                                    -> c_int;
     /// `int sd_journal_next(sd_journal *j);`
     ///
     /// <https://www.freedesktop.org/software/systemd/man/sd_journal_next.html#>
@@ -129,6 +360,11 @@ extern "C" {
     ///
     /// <https://www.freedesktop.org/software/systemd/man/sd_journal_next.html#>
     pub fn sd_journal_previous_skip(journal: *mut sd_journal, skip: u64) -> c_int;
+    /// `int sd_journal_step_one(sd_journal *j, int advanced);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_journal_next.html#>
+    #[cfg(systemd_v254)]
+    pub fn sd_journal_step_one(journal: *mut sd_journal, advanced: c_int) -> c_int;
     /// `int sd_journal_get_realtime_usec(sd_journal *j, uint64_t *usec);`
     ///
     /// <https://www.freedesktop.org/software/systemd/man/sd_journal_get_realtime_usec.html#>
@@ -141,6 +377,15 @@ extern "C" {
                                          usec: *mut u64,
                                          boot_id: *mut sd_id128)
                                          -> c_int;
+    /// `int sd_journal_get_seqnum(sd_journal *j, uint64_t *ret_seqnum,
+    ///                                 sd_id128_t *ret_seqnum_id);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_journal_get_seqnum.html#>
+    #[cfg(systemd_v254)]
+    pub fn sd_journal_get_seqnum(journal: *mut sd_journal,
+                                 seqnum: *mut u64,
+                                 seqnum_id: *mut sd_id128)
+                                 -> c_int;
     /// `int sd_journal_add_match(sd_journal *j, const void *data,
     ///                                 size_t size);`
     ///
@@ -289,6 +534,7 @@ extern "C" {
     ///                                 const void **data, size_t *length);`
     ///
     /// <https://www.freedesktop.org/software/systemd/man/sd_journal_get_data.html#>
+    #[cfg(systemd_v246)]
     pub fn sd_journal_enumerate_available_data(journal: *mut sd_journal,
                                                data: *mut *const c_void,
                                                length: *mut size_t)
@@ -313,6 +559,7 @@ extern "C" {
     ///                                 const void **data, size_t *length);`
     ///
     /// <https://www.freedesktop.org/software/systemd/man/sd_journal_query_unique.html#>
+    #[cfg(systemd_v246)]
     pub fn sd_journal_enumerate_available_unique(journal: *mut sd_journal,
                                                  data: *mut *const c_void,
                                                  length: *mut size_t)
@@ -330,3 +577,88 @@ extern "C" {
     /// <https://www.freedesktop.org/software/systemd/man/sd_journal_query_unique.html#>
     pub fn sd_journal_restart_unique(journal: *mut sd_journal);
 }
+
+// bindings taking a `va_list` require the unstable `c_variadic` language
+// feature and are therefore only available on nightly with feature `va_list`
+#[cfg(feature = "va_list")]
+sd_extern! {
+    /// `int sd_journal_printv(int priority, const char *format, va_list ap);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_journal_print.html#>
+    pub fn sd_journal_printv(priority: c_int, message: *const c_char, args: VaList) -> c_int;
+    /// `int sd_journal_printv_with_location(int priority, const char *file,
+    ///          const char *line, const char *func, const char *format,
+    ///          va_list ap);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_journal_print.html#>
+    pub fn sd_journal_printv_with_location(priority: c_int,
+                                           file: *const c_char,
+                                           line: *const c_char,
+                                           func: *const c_char,
+                                           message: *const c_char,
+                                           args: VaList)
+                                           -> c_int;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+
+    #[cfg(systemd_v246)]
+    const FUNCTIONS: [OpenFunction; 5] = [OpenFunction::Open,
+                                          OpenFunction::Directory,
+                                          OpenFunction::DirectoryFd,
+                                          OpenFunction::Files,
+                                          OpenFunction::FilesFd];
+
+    #[cfg(systemd_v246)]
+    #[test]
+    fn take_directory_fd() {
+        for &function in &FUNCTIONS {
+            let result = OpenFlags::TAKE_DIRECTORY_FD.check(function);
+            if function == OpenFunction::DirectoryFd {
+                assert_eq!(result, Ok(SD_JOURNAL_TAKE_DIRECTORY_FD));
+            } else {
+                assert_eq!(result, Err(OpenFlags::TAKE_DIRECTORY_FD), "{:?}", function);
+            }
+        }
+    }
+
+    #[test]
+    fn local_only() {
+        let flags = OpenFlags::LOCAL_ONLY | OpenFlags::SYSTEM;
+        assert_eq!(flags.check(OpenFunction::Open),
+                   Ok(SD_JOURNAL_LOCAL_ONLY | SD_JOURNAL_SYSTEM));
+        assert_eq!(flags.check(OpenFunction::Directory),
+                   Err(OpenFlags::LOCAL_ONLY));
+        assert_eq!(flags.check(OpenFunction::Files), Err(flags));
+        // libsystemd agrees
+        let mut journal = ptr::null_mut();
+        let path = b"/var/log/journal\0".as_ptr() as *const c_char;
+        let paths = [path, ptr::null()];
+        unsafe {
+            assert_eq!(sd_journal_open_directory(&mut journal, path, SD_JOURNAL_LOCAL_ONLY),
+                       -libc::EINVAL);
+            assert_eq!(sd_journal_open_files(&mut journal, paths.as_ptr(), SD_JOURNAL_LOCAL_ONLY),
+                       -libc::EINVAL);
+        }
+    }
+
+    #[cfg(systemd_v254)]
+    #[test]
+    fn assume_immutable() {
+        for &function in &FUNCTIONS {
+            assert_eq!(OpenFlags::ASSUME_IMMUTABLE.check(function),
+                       Ok(SD_JOURNAL_ASSUME_IMMUTABLE));
+        }
+    }
+
+    #[test]
+    fn from_bits() {
+        assert_eq!(OpenFlags::from_bits(OpenFlags::all().bits()),
+                   Some(OpenFlags::all()));
+        assert_eq!(OpenFlags::from_bits(1 << 30), None);
+        assert!(OpenFlags::empty().check(OpenFunction::FilesFd).is_ok());
+    }
+}
@@ -16,11 +16,90 @@
 
 //! [![GitHub release (latest by date)](https://img.shields.io/github/v/release/ente76/sd-sys?label=github&logo=github)](https://github.com/ente76/sd-sys)  [![Crates.io](https://img.shields.io/crates/v/sd-sys)](https://crates.io/crates/sd-sys)  [![docs.rs](https://docs.rs/sd-sys/badge.svg)](https://docs.rs/sd-sys/)  ![GitHub Workflow Status](https://img.shields.io/github/workflow/status/ente76/sd-sys/test?label=test&logo=github) [![buy me a coffee](https://img.shields.io/badge/buy%20me%20a%20coffee-or%20I%20sing-53a0d0?style=flat&logo=Buy-Me-A-Coffee)](https://www.buymeacoffee.com/ente)  [![donate@paypal](https://img.shields.io/badge/paypal-donation-53a0d0?style=flat&logo=paypal)](https://www.paypal.com/donate?hosted_button_id=CRGNTJBS4AD4G)  
 //!
-//! [sd-sys](https://github.com/ente76/sd-sys) defines the FFI bindings for sd-id128 & sd-journal in the systemd API of [libsystemd](https://www.freedesktop.org/software/systemd/man/sd-id128.html).  sd-sys is part of the [systemd.rs](https://github.com/ente76/systemd.rs) project, providing the FFI bindings for [sd-id128](https://github.com/ente76/sd-id128) and [sd-journal](https://github.com/ente76/sd-journal).
+//! [sd-sys](https://github.com/ente76/sd-sys) defines the FFI bindings for sd-bus, sd-daemon, sd-id128, sd-journal & sd-login in the systemd API of [libsystemd](https://www.freedesktop.org/software/systemd/man/sd-id128.html).  sd-sys is part of the [systemd.rs](https://github.com/ente76/systemd.rs) project, providing the FFI bindings for [sd-id128](https://github.com/ente76/sd-id128) and [sd-journal](https://github.com/ente76/sd-journal).
 //!
 //! ## Compatibility
 //!
-//! This library is developed against the latest version of libsystemd. As such there may be FFI bindings included which are not available in previous versions. Issues may arise during linking, if an unavailable function is used. There is no version check included in this library. For an example of such version check, see the feature definition of [sd-id128](https://github.com/ente76/sd-id128).
+//! This library is developed against the latest version of libsystemd. Bindings
+//! available since systemd 239 are always included, bindings introduced in
+//! later versions are gated by the version which introduced them. Linking fails
+//! if a binding is enabled which the installed libsystemd does not provide.
+//!
+//! ## Features
+//!
+//! - `dlopen`: resolve the bindings at runtime from `libsystemd.so.0` instead
+//!   of linking against libsystemd. A binding whose symbol is not available
+//!   returns `-ENOSYS` (or a null pointer) instead of failing to link, so the
+//!   `systemd_v*` features may be set to the newest version the application can
+//!   make use of. Variadic functions (`sd_journal_print`, `sd_journal_send`,
+//!   `sd_bus_call_method`, ...) cannot be defined in Rust and are not available
+//!   in this mode.
+//! - `serde`: `Serialize` and `Deserialize` for `sd_id128` as string of 32
+//!   hexadecimal characters.
+//! - `uuid`: conversion between `sd_id128` and `uuid::Uuid`.
+//! - `static`: link `libsystemd.a` and the libraries it depends on as reported
+//!   by `pkg-config --static` (e.g. libcap, liblz4, libzstd, liblzma,
+//!   libgcrypt) instead of `libsystemd.so`. Mutually exclusive with `dlopen`.
+//! - `systemd_v239` ... `systemd_v256`: enable the bindings introduced up to
+//!   and including the given systemd version. Each feature implies the previous
+//!   one. `systemd_v246` is enabled by default; disable the default features to
+//!   target older systems, e.g. `default-features = false, features =
+//!   ["systemd_v239"]` for RHEL 8.
+//! - `systemd_auto`: enable the bindings up to the version of the libsystemd
+//!   found by pkg-config at build time. The detected version only adds to the
+//!   `systemd_v*` features, it never disables bindings another crate enabled
+//!   with them.
+//! - `va_list`: bindings taking a `va_list` argument (`sd_journal_printv`,
+//!   `sd_journal_printv_with_location`, `sd_bus_call_methodv`, ...). Requires a
+//!   nightly compiler as Rust's `VaList` is not stable yet.
+//!
+//! ## Build
+//!
+//! libsystemd is located with pkg-config. The following environment variables
+//! change how libsystemd is linked:
+//!
+//! - `SD_SYS_LIB_DIR`: link libsystemd from the given directory without
+//!   consulting pkg-config.
+//! - `SD_SYS_STATIC`: same as feature `static` (any value but `0`).
+//! - `DOCS_RS`: nothing is linked when building the documentation on docs.rs.
+//!
+//! If pkg-config fails to find libsystemd, the build falls back to the default
+//! search path of the linker and reports the cause as build warning.
+//!
+//! ## Migration from 1.x
+//!
+//! - `sd_id128` is now the C union of `bytes` and `qwords`. Replace `id.value`
+//!   with `id.bytes()` and `sd_id128 { value }` with
+//!   `sd_id128::from_bytes(value)`.
+//! - `sd_id128_to_string` takes the output buffer as `*mut c_char`.
+//! - Bindings introduced after systemd 246 require the matching `systemd_v*`
+//!   feature.
+
+#![cfg_attr(feature = "va_list", feature(c_variadic))]
+
+#[cfg(all(feature = "dlopen", feature = "static"))]
+compile_error!("features `dlopen` and `static` are mutually exclusive");
+
+#[macro_use]
+mod ffi;
+mod hmac;
+
+/// FFI binding for sd-bus as defined in libsystemd
+///
+/// Covers bus connections, synchronous and asynchronous method calls and
+/// replies, signal matches, the construction and parsing of messages and the
+/// export of objects described by static vtables. Variadic functions such as
+/// `sd_bus_call_method` or `sd_bus_message_append` take the arguments as
+/// described by the D-Bus type signature `types`, see the `SD_BUS_TYPE_*`
+/// constants.
+pub mod bus;
+
+/// FFI binding for sd-daemon as defined in libsystemd
+///
+/// Covers the notification of the service manager about state changes, the
+/// file descriptor store, socket activation, the watchdog and the detection of
+/// systemd.
+pub mod daemon;
 
 /// FFI binding for sd-id128 as defined in libsystemd used in
 /// [sd-id128](https://gitlab.com/systemd.rs/sd-id128)
@@ -37,3 +116,12 @@ pub mod id128;
 /// wrapper to the FFI bindings which translates each extern function into
 /// native rust.
 pub mod journal;
+
+/// FFI binding for sd-login as defined in libsystemd
+///
+/// Covers the sessions, seats, users and machines known to systemd-logind and
+/// the lookup of the session, unit or user a process belongs to. Changes are
+/// observed with a login monitor, polled like a journal. Functions
+/// returning arrays of strings allocate them with `malloc`, release them with
+/// [`login::free_strv`].
+pub mod login;
@@ -0,0 +1,325 @@
+// sd-sys: FFI bindings to systemd for sd-id128 & sd-journal
+// Copyright (C) 2020 Christian Klaue [mail@ck76.de]
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use libc::{c_char, c_int, c_uint, c_void, pid_t, uid_t};
+
+/// FFI data type mapping for the login monitor as defined in libsystemd
+///
+/// Created by `sd_login_monitor_new` for one of the categories `"seat"`,
+/// `"session"`, `"uid"` and `"machine"` or null for all of them.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct sd_login_monitor {
+    _unused: [u8; 0]
+}
+
+/// Frees a `NULL` terminated array of strings and the strings it contains as
+/// returned by `sd_get_seats`, `sd_uid_get_sessions`, `sd_seat_get_sessions`,
+/// ...
+///
+/// # Safety
+///
+/// `strv` must be null or a `NULL` terminated array returned by libsystemd. It
+/// must not be used afterwards.
+pub unsafe fn free_strv(strv: *mut *mut c_char) {
+    if strv.is_null() {
+        return;
+    }
+    let mut entry = strv;
+    while !(*entry).is_null() {
+        libc::free(*entry as *mut c_void);
+        entry = entry.add(1);
+    }
+    libc::free(strv as *mut c_void);
+}
+
+sd_extern! {
+    /// `int sd_pid_get_session(pid_t pid, char **session);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_pid_get_session(pid: pid_t, session: *mut *mut c_char) -> c_int;
+    /// `int sd_pid_get_owner_uid(pid_t pid, uid_t *uid);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_pid_get_owner_uid(pid: pid_t, uid: *mut uid_t) -> c_int;
+    /// `int sd_pid_get_unit(pid_t pid, char **unit);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_pid_get_unit(pid: pid_t, unit: *mut *mut c_char) -> c_int;
+    /// `int sd_pid_get_user_unit(pid_t pid, char **unit);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_pid_get_user_unit(pid: pid_t, unit: *mut *mut c_char) -> c_int;
+    /// `int sd_pid_get_slice(pid_t pid, char **slice);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_pid_get_slice(pid: pid_t, slice: *mut *mut c_char) -> c_int;
+    /// `int sd_pid_get_user_slice(pid_t pid, char **slice);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_pid_get_user_slice(pid: pid_t, slice: *mut *mut c_char) -> c_int;
+    /// `int sd_pid_get_machine_name(pid_t pid, char **machine);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_pid_get_machine_name(pid: pid_t, machine: *mut *mut c_char) -> c_int;
+    /// `int sd_pid_get_cgroup(pid_t pid, char **cgroup);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_pid_get_cgroup(pid: pid_t, cgroup: *mut *mut c_char) -> c_int;
+    /// `int sd_pidfd_get_session(int pidfd, char **session);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    #[cfg(systemd_v253)]
+    pub fn sd_pidfd_get_session(pidfd: c_int, session: *mut *mut c_char) -> c_int;
+    /// `int sd_pidfd_get_owner_uid(int pidfd, uid_t *uid);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    #[cfg(systemd_v253)]
+    pub fn sd_pidfd_get_owner_uid(pidfd: c_int, uid: *mut uid_t) -> c_int;
+    /// `int sd_pidfd_get_unit(int pidfd, char **unit);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    #[cfg(systemd_v253)]
+    pub fn sd_pidfd_get_unit(pidfd: c_int, unit: *mut *mut c_char) -> c_int;
+    /// `int sd_pidfd_get_user_unit(int pidfd, char **unit);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    #[cfg(systemd_v253)]
+    pub fn sd_pidfd_get_user_unit(pidfd: c_int, unit: *mut *mut c_char) -> c_int;
+    /// `int sd_pidfd_get_slice(int pidfd, char **slice);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    #[cfg(systemd_v253)]
+    pub fn sd_pidfd_get_slice(pidfd: c_int, slice: *mut *mut c_char) -> c_int;
+    /// `int sd_pidfd_get_user_slice(int pidfd, char **slice);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    #[cfg(systemd_v253)]
+    pub fn sd_pidfd_get_user_slice(pidfd: c_int, slice: *mut *mut c_char) -> c_int;
+    /// `int sd_pidfd_get_machine_name(int pidfd, char **machine);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    #[cfg(systemd_v253)]
+    pub fn sd_pidfd_get_machine_name(pidfd: c_int, machine: *mut *mut c_char) -> c_int;
+    /// `int sd_pidfd_get_cgroup(int pidfd, char **cgroup);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    #[cfg(systemd_v253)]
+    pub fn sd_pidfd_get_cgroup(pidfd: c_int, cgroup: *mut *mut c_char) -> c_int;
+    /// `int sd_peer_get_session(int fd, char **session);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_peer_get_session(fd: c_int, session: *mut *mut c_char) -> c_int;
+    /// `int sd_peer_get_owner_uid(int fd, uid_t *uid);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_peer_get_owner_uid(fd: c_int, uid: *mut uid_t) -> c_int;
+    /// `int sd_peer_get_unit(int fd, char **unit);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_peer_get_unit(fd: c_int, unit: *mut *mut c_char) -> c_int;
+    /// `int sd_peer_get_user_unit(int fd, char **unit);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_peer_get_user_unit(fd: c_int, unit: *mut *mut c_char) -> c_int;
+    /// `int sd_peer_get_slice(int fd, char **slice);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_peer_get_slice(fd: c_int, slice: *mut *mut c_char) -> c_int;
+    /// `int sd_peer_get_user_slice(int fd, char **slice);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_peer_get_user_slice(fd: c_int, slice: *mut *mut c_char) -> c_int;
+    /// `int sd_peer_get_machine_name(int fd, char **machine);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_peer_get_machine_name(fd: c_int, machine: *mut *mut c_char) -> c_int;
+    /// `int sd_peer_get_cgroup(int fd, char **cgroup);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_pid_get_session.html#>
+    pub fn sd_peer_get_cgroup(fd: c_int, cgroup: *mut *mut c_char) -> c_int;
+    /// `int sd_uid_get_state(uid_t uid, char **state);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_uid_get_state.html#>
+    pub fn sd_uid_get_state(uid: uid_t, state: *mut *mut c_char) -> c_int;
+    /// `int sd_uid_get_display(uid_t uid, char **session);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_uid_get_state.html#>
+    pub fn sd_uid_get_display(uid: uid_t, session: *mut *mut c_char) -> c_int;
+    /// `int sd_uid_get_login_time(uid_t uid, uint64_t *usec);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_uid_get_state.html#>
+    #[cfg(systemd_v254)]
+    pub fn sd_uid_get_login_time(uid: uid_t, usec: *mut u64) -> c_int;
+    /// `int sd_uid_is_on_seat(uid_t uid, int require_active, const char *seat);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_uid_get_state.html#>
+    pub fn sd_uid_is_on_seat(uid: uid_t, require_active: c_int, seat: *const c_char) -> c_int;
+    /// `int sd_uid_get_sessions(uid_t uid, int require_active, char ***sessions);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_uid_get_state.html#>
+    pub fn sd_uid_get_sessions(uid: uid_t,
+                               require_active: c_int,
+                               sessions: *mut *mut *mut c_char)
+                               -> c_int;
+    /// `int sd_uid_get_seats(uid_t uid, int require_active, char ***seats);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_uid_get_state.html#>
+    pub fn sd_uid_get_seats(uid: uid_t, require_active: c_int, seats: *mut *mut *mut c_char) -> c_int;
+    /// `int sd_session_is_active(const char *session);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    pub fn sd_session_is_active(session: *const c_char) -> c_int;
+    /// `int sd_session_is_remote(const char *session);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    pub fn sd_session_is_remote(session: *const c_char) -> c_int;
+    /// `int sd_session_get_state(const char *session, char **state);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    pub fn sd_session_get_state(session: *const c_char, state: *mut *mut c_char) -> c_int;
+    /// `int sd_session_get_uid(const char *session, uid_t *uid);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    pub fn sd_session_get_uid(session: *const c_char, uid: *mut uid_t) -> c_int;
+    /// `int sd_session_get_username(const char *session, char **username);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    #[cfg(systemd_v254)]
+    pub fn sd_session_get_username(session: *const c_char, username: *mut *mut c_char) -> c_int;
+    /// `int sd_session_get_seat(const char *session, char **seat);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    pub fn sd_session_get_seat(session: *const c_char, seat: *mut *mut c_char) -> c_int;
+    /// `int sd_session_get_start_time(const char *session, uint64_t *usec);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    #[cfg(systemd_v254)]
+    pub fn sd_session_get_start_time(session: *const c_char, usec: *mut u64) -> c_int;
+    /// `int sd_session_get_service(const char *session, char **service);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    pub fn sd_session_get_service(session: *const c_char, service: *mut *mut c_char) -> c_int;
+    /// `int sd_session_get_type(const char *session, char **type);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    pub fn sd_session_get_type(session: *const c_char, kind: *mut *mut c_char) -> c_int;
+    /// `int sd_session_get_class(const char *session, char **clazz);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    pub fn sd_session_get_class(session: *const c_char, class: *mut *mut c_char) -> c_int;
+    /// `int sd_session_get_desktop(const char *session, char **desktop);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    pub fn sd_session_get_desktop(session: *const c_char, desktop: *mut *mut c_char) -> c_int;
+    /// `int sd_session_get_display(const char *session, char **display);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    pub fn sd_session_get_display(session: *const c_char, display: *mut *mut c_char) -> c_int;
+    /// `int sd_session_get_leader(const char *session, pid_t *leader);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    #[cfg(systemd_v254)]
+    pub fn sd_session_get_leader(session: *const c_char, leader: *mut pid_t) -> c_int;
+    /// `int sd_session_get_remote_user(const char *session, char **remote_user);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    pub fn sd_session_get_remote_user(session: *const c_char, remote_user: *mut *mut c_char) -> c_int;
+    /// `int sd_session_get_remote_host(const char *session, char **remote_host);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    pub fn sd_session_get_remote_host(session: *const c_char, remote_host: *mut *mut c_char) -> c_int;
+    /// `int sd_session_get_tty(const char *session, char **display);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    pub fn sd_session_get_tty(session: *const c_char, tty: *mut *mut c_char) -> c_int;
+    /// `int sd_session_get_vt(const char *session, unsigned *vtnr);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_session_is_active.html#>
+    pub fn sd_session_get_vt(session: *const c_char, vt: *mut c_uint) -> c_int;
+    /// `int sd_seat_get_active(const char *seat, char **session, uid_t *uid);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_seat_get_active.html#>
+    pub fn sd_seat_get_active(seat: *const c_char, session: *mut *mut c_char, uid: *mut uid_t) -> c_int;
+    /// `int sd_seat_get_sessions(const char *seat, char ***ret_sessions,
+    ///             uid_t **ret_uids, unsigned *ret_n_uids);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_seat_get_active.html#>
+    pub fn sd_seat_get_sessions(seat: *const c_char,
+                                sessions: *mut *mut *mut c_char,
+                                uids: *mut *mut uid_t,
+                                count: *mut c_uint)
+                                -> c_int;
+    /// `int sd_seat_can_multi_session(const char *seat);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_seat_get_active.html#>
+    pub fn sd_seat_can_multi_session(seat: *const c_char) -> c_int;
+    /// `int sd_seat_can_tty(const char *seat);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_seat_get_active.html#>
+    pub fn sd_seat_can_tty(seat: *const c_char) -> c_int;
+    /// `int sd_seat_can_graphical(const char *seat);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_seat_get_active.html#>
+    pub fn sd_seat_can_graphical(seat: *const c_char) -> c_int;
+    /// `int sd_machine_get_class(const char *machine, char **clazz);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_machine_get_class.html#>
+    pub fn sd_machine_get_class(machine: *const c_char, class: *mut *mut c_char) -> c_int;
+    /// `int sd_machine_get_ifindices(const char *machine, int **ret_ifindices);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_machine_get_class.html#>
+    pub fn sd_machine_get_ifindices(machine: *const c_char, ifindices: *mut *mut c_int) -> c_int;
+    /// `int sd_get_seats(char ***seats);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_get_seats.html#>
+    pub fn sd_get_seats(seats: *mut *mut *mut c_char) -> c_int;
+    /// `int sd_get_sessions(char ***sessions);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_get_seats.html#>
+    pub fn sd_get_sessions(sessions: *mut *mut *mut c_char) -> c_int;
+    /// `int sd_get_uids(uid_t **users);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_get_seats.html#>
+    pub fn sd_get_uids(users: *mut *mut uid_t) -> c_int;
+    /// `int sd_get_machine_names(char ***machines);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_get_seats.html#>
+    pub fn sd_get_machine_names(machines: *mut *mut *mut c_char) -> c_int;
+    /// `int sd_login_monitor_new(const char *category, sd_login_monitor** ret);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_login_monitor_new.html#>
+    pub fn sd_login_monitor_new(category: *const c_char, monitor: *mut *mut sd_login_monitor) -> c_int;
+    /// `sd_login_monitor *sd_login_monitor_unref(sd_login_monitor *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_login_monitor_new.html#>
+    pub fn sd_login_monitor_unref(monitor: *mut sd_login_monitor) -> *mut sd_login_monitor;
+    /// `int sd_login_monitor_flush(sd_login_monitor *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_login_monitor_new.html#>
+    pub fn sd_login_monitor_flush(monitor: *mut sd_login_monitor) -> c_int;
+    /// `int sd_login_monitor_get_fd(sd_login_monitor *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_login_monitor_new.html#>
+    pub fn sd_login_monitor_get_fd(monitor: *mut sd_login_monitor) -> c_int;
+    /// `int sd_login_monitor_get_events(sd_login_monitor *m);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_login_monitor_new.html#>
+    pub fn sd_login_monitor_get_events(monitor: *mut sd_login_monitor) -> c_int;
+    /// `int sd_login_monitor_get_timeout(sd_login_monitor *m, uint64_t *timeout_usec);`
+    ///
+    /// <https://www.freedesktop.org/software/systemd/man/sd_login_monitor_new.html#>
+    pub fn sd_login_monitor_get_timeout(monitor: *mut sd_login_monitor, timeout: *mut u64) -> c_int;
+}